@@ -31,19 +31,46 @@ str_to_num = {
     Tile.WALL: 2,
     Tile.LIQUID: 3
 }
+# legend of ascii maps, same as `TileType::symbol` in src/map.rs
+str_to_symbol = {
+    Tile.NONE: ' ',
+    Tile.FLOOR: '.',
+    Tile.WALL: '#',
+    Tile.LIQUID: '~'
+}
+symbol_to_str = {symbol: tile for tile, symbol in str_to_symbol.items()}
+# entity letters from src/loader.rs, kept as they are (each stands on a floor tile)
+ENTITIES = 'PEcpkfb'
+ENTITY_COLOR = Color(255, 128, 0)
+
+def parse_symbol(symbol):
+    if symbol in ENTITIES:
+        return symbol
+    if symbol not in symbol_to_str:
+        raise ValueError(f'unknown symbol {symbol!r}')
+    return symbol_to_str[symbol]
 
 CELL = 20
 try:
     with open(f'{argv[1]}') as file:
         obj =  json.load(file)
         # print(argv[1], obj)
-        tilemap = obj['tilemap']
-        tilemap = [
-            [num_to_str[tile] for tile in row]
-            for row in tilemap
-        ]
-except Exception:
+        if 'map' in obj:
+            tilemap = [
+                [parse_symbol(symbol) for symbol in row]
+                for row in obj['map']
+            ]
+        else:
+            tilemap = obj['tilemap']
+            tilemap = [
+                [num_to_str[tile] for tile in row]
+                for row in tilemap
+            ]
+except (IndexError, FileNotFoundError):
+    # no level given, or a new one: start from scratch
     tilemap = [[Tile.FLOOR]]
+except (OSError, ValueError, KeyError, TypeError) as e:
+    exit(f'Failed to load {argv[1]}: {e!r}')
 print(tilemap)
 
 brushes = [Tile.FLOOR, Tile.WALL, Tile.LIQUID]
@@ -124,7 +151,7 @@ while running:
     
     for i, row in enumerate(tilemap):
         for j, tile in enumerate(row):
-            pg.draw.rect(window, colors[tile], Rect(CELL*j+1, CELL*i+1, CELL-1, CELL-1))
+            pg.draw.rect(window, colors.get(tile, ENTITY_COLOR), Rect(CELL*j+1, CELL*i+1, CELL-1, CELL-1))
 
     pg.display.flip()
     clock.tick(30)
@@ -138,5 +165,5 @@ while running:
 normalize_tilemap()
 print('[')
 for i, row in enumerate(tilemap):
-    print(f'    {[str_to_num[tile] for tile in row]}{"," if i+1!=len(tilemap) else ""}')
+    print(f'    {json.dumps("".join(str_to_symbol.get(tile, tile) for tile in row))}{"," if i+1!=len(tilemap) else ""}')
 print(']')
\ No newline at end of file
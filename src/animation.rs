@@ -1,14 +1,22 @@
 
-#[derive(Clone)]
+use std::time::Duration;
+
+use crate::clock::TICK;
+use crate::sprites::SpriteAnimation;
+
+// movement of an entity along a path, advanced one tick at a time
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Animation {
     pub path: Vec<(i32, i32)>,
-    pub sprites: Vec<String>,
+    pub sprites: SpriteAnimation,
     pub current: usize,
 }
 
 impl Animation {
-    pub fn init(points: Vec<(i32, i32)>, sprites: Vec<String>, duration: i32) -> Self {
+    // duration is the time spent moving between two points
+    pub fn init(points: Vec<(i32, i32)>, sprites: SpriteAnimation, duration: Duration) -> Self {
         println!("Animation started");
+        let duration = (duration.as_millis() / TICK.as_millis()).max(1) as i32;
         let mut path: Vec<(i32, i32)> = vec![];
         for i in 0..points.len()-1 {
             let (pcx, pcy) = points[i];   // curr point
@@ -33,7 +41,18 @@ impl Animation {
         if !self.finished() {
             flipped = Some(self.path[self.current-1].0-self.path[self.current].0 > 0);
         }
-        (self.path[self.current-1], self.sprites[self.current%self.sprites.len()].clone(), self.finished(), flipped)
+        (self.path[self.current-1], self.sprite().to_string(), self.finished(), flipped)
+    }
+
+    pub fn sprite(&self) -> &str {
+        self.sprites.frame_at(self.current as u64 * TICK.as_millis() as u64)
+    }
+
+    // position between the last point returned by `next_frame` and the next one
+    pub fn position(&self, alpha: f32) -> (i32, i32) {
+        let (x, y) = self.path[self.current.max(1) - 1];
+        let (next_x, next_y) = self.path[self.current.min(self.path.len() - 1)];
+        (x + ((next_x - x) as f32 * alpha) as i32, y + ((next_y - y) as f32 * alpha) as i32)
     }
 
     pub fn finished(&self) -> bool {
@@ -0,0 +1,53 @@
+use std::time::{Duration, Instant};
+
+// game logic advances in fixed steps of this length, rendering happens as often as the screen allows
+pub const TICK: Duration = Duration::from_millis(20);
+// frames are capped to this rate without vsync, so menus don't keep a CPU core busy
+const MAX_FPS: u32 = 120;
+// after a long stall (window dragged, pause menu) don't try to catch up on more than this
+const MAX_TICKS_PER_FRAME: u32 = 10;
+
+// paces the scene loop: call `frame` once per frame, run the returned number of ticks,
+// then draw using `alpha` to interpolate between the last two ticks
+pub struct Clock {
+    last_frame: Instant,
+    // time not yet used up by ticks
+    accumulator: Duration
+}
+
+impl Clock {
+    pub fn init() -> Self {
+        Self { last_frame: Instant::now(), accumulator: Duration::ZERO }
+    }
+
+    // forget the time spent outside the loop, e.g. in a menu
+    pub fn reset(&mut self) {
+        self.last_frame = Instant::now();
+        self.accumulator = Duration::ZERO;
+    }
+
+    // wait for the next frame, returns the number of ticks to run before drawing it
+    pub fn frame(&mut self) -> u32 {
+        let next_frame = self.last_frame + Duration::from_secs(1) / MAX_FPS;
+        let now = Instant::now();
+        if now < next_frame {
+            std::thread::sleep(next_frame - now);
+        }
+        let now = Instant::now();
+        self.accumulator += now - self.last_frame;
+        self.last_frame = now;
+
+        let ticks = (self.accumulator.as_nanos() / TICK.as_nanos()) as u32;
+        if ticks > MAX_TICKS_PER_FRAME {
+            self.accumulator = Duration::ZERO;
+            return MAX_TICKS_PER_FRAME;
+        }
+        self.accumulator -= TICK * ticks;
+        ticks
+    }
+
+    // how far the current frame is between the last tick and the next one, from 0 to 1
+    pub fn alpha(&self) -> f32 {
+        self.accumulator.as_secs_f32() / TICK.as_secs_f32()
+    }
+}
@@ -0,0 +1,26 @@
+// optional pickups scattered around a level, collected by walking over them
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollectibleKind { Fish, Bell }
+
+impl CollectibleKind {
+    pub fn sprite(&self) -> &'static str {
+        match self {
+            CollectibleKind::Fish => "fish",
+            CollectibleKind::Bell => "bell"
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Collectible {
+    pub pos: (usize, usize),
+    pub kind: CollectibleKind,
+    pub collected: bool
+}
+
+impl Collectible {
+    pub fn init(pos: (usize, usize), kind: CollectibleKind) -> Self {
+        Self { pos, kind, collected: false }
+    }
+}
@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+use sdl2::GameControllerSubsystem;
+use sdl2::controller::{Axis, GameController};
+use sdl2::event::Event;
+
+// how far a stick has to be pushed before it counts as a press
+const DEAD_ZONE: i16 = 16_000;
+
+// keeps game controllers open as they are plugged in and out,
+// SDL also sends an "added" event for every controller connected at startup
+pub struct Controllers {
+    subsystem: GameControllerSubsystem,
+    open: HashMap<u32, GameController>,
+    // direction each stick axis is currently pushed in (-1, 0 or 1)
+    held: HashMap<Axis, i32>
+}
+
+impl Controllers {
+    pub fn init(subsystem: GameControllerSubsystem) -> Self {
+        Self { subsystem, open: HashMap::new(), held: HashMap::new() }
+    }
+
+    pub fn handle_event(&mut self, event: &Event) {
+        match *event {
+            Event::ControllerDeviceAdded { which, .. } => {
+                match self.subsystem.open(which) {
+                    Ok(controller) => {
+                        println!("Controller connected: {}", controller.name());
+                        self.open.insert(controller.instance_id(), controller);
+                    },
+                    Err(e) => { println!("Failed to open controller {which}: {e}") }
+                }
+            },
+            Event::ControllerDeviceRemoved { which, .. } => {
+                if let Some(controller) = self.open.remove(&which) {
+                    println!("Controller disconnected: {}", controller.name());
+                }
+            },
+            _ => {}
+        }
+    }
+
+    // turn stick motion into single steps: a step is taken when the stick leaves the dead zone,
+    // and not again until it comes back
+    pub fn stick_step(&mut self, axis: Axis, value: i16) -> Option<(isize, isize)> {
+        let direction = match value {
+            v if v > DEAD_ZONE => 1,
+            v if v < -DEAD_ZONE => -1,
+            _ => 0
+        };
+        let previous = self.held.insert(axis, direction).unwrap_or(0);
+        if direction == 0 || direction == previous {
+            return None;
+        }
+        match axis {
+            Axis::LeftX => Some((0, direction as isize)),
+            Axis::LeftY => Some((direction as isize, 0)),
+            _ => None
+        }
+    }
+}
@@ -0,0 +1,263 @@
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::WindowCanvas;
+use sdl2::event::{Event, WindowEvent};
+use sdl2::keyboard::{Keycode, Mod};
+use sdl2::mouse::{Cursor, SystemCursor, MouseButton, MouseWheelDirection};
+use std::time::Instant;
+
+use crate::hud::Hud;
+use crate::input::{self, Action};
+use crate::loader::{self, LevelFile};
+use crate::map::{Map, TileType};
+use crate::menu::{Menu, MenuAction};
+use crate::scene::{Context, Scene, Transition};
+use crate::sprites::{Pose, Sprites};
+use crate::LEVELS;
+
+// walls are drawn as flat tiles, so the layout can be seen from any side
+const WALL_COLOR: Color = Color::RGB(110, 120, 140);
+
+// tiles the brush cycles through
+const BRUSHES: [TileType; 4] = [TileType::Floor, TileType::Wall, TileType::Liquid, TileType::None];
+
+// level editor: paint tiles over a level's map and save it as an ascii map,
+// entities, lights and objectives are kept as they are
+pub struct Editor<'a> {
+    index: usize,
+    level: LevelFile,
+    tilemap: Map,
+    sprites: Sprites<'a>,
+    hud: Hud<'a>,
+    brush: usize,
+    // tile under the mouse pointer
+    hovered: Option<(usize, usize)>,
+    // painted since the last save
+    changed: bool,
+    started: Instant,
+    _cursor: Cursor
+}
+
+impl<'a> Editor<'a> {
+    pub fn init(ctx: &mut Context<'a>, index: usize) -> Self {
+        println!("SHOW EDITOR");
+        let cursor = Cursor::from_system(SystemCursor::Crosshair).unwrap();
+        cursor.set();
+
+        let level = loader::load_level(Self::path(index));
+        let mut tilemap = Map::new();
+        tilemap.load(level.tilemap.clone());
+        let sprites = Sprites::load(ctx.texture_creator, Some(&level.name));
+
+        Self {
+            index,
+            level,
+            tilemap,
+            sprites,
+            hud: Hud::init(),
+            brush: 0,
+            hovered: None,
+            changed: false,
+            started: Instant::now(),
+            _cursor: cursor
+        }
+    }
+
+    fn path(index: usize) -> String {
+        format!("resources/levels/{}.json", LEVELS[index])
+    }
+
+    // tile under the mouse pointer, if it is inside the map
+    fn tile_at(&self, canvas: &WindowCanvas, x: i32, y: i32) -> Option<(usize, usize)> {
+        let (x, y) = input::to_pixels(canvas, x, y);
+        let x = ((x - self.tilemap.translation_x) as f32 / self.tilemap.scale).floor() as i32;
+        let y = ((y - self.tilemap.translation_y) as f32 / self.tilemap.scale).floor() as i32;
+        // same as `Map::get_tile_index`, which doesn't expect points outside the map
+        let (row, col) = ((2*y-x).div_euclid(28), (2*y+x).div_euclid(28));
+        // the padding around the tilemap can't be painted
+        let inside = row >= 1 && (row as usize) + 1 < self.tilemap.tiles.len()
+            && col >= 1 && (col as usize) + 1 < self.tilemap.tiles[row as usize].len();
+        inside.then_some((row as usize, col as usize))
+    }
+
+    // entities stand on floor tiles, so the tiles under them stay as they are
+    fn occupied(&self, tile: (usize, usize)) -> bool {
+        let level = &self.level;
+        tile == level.player || tile == level.exit
+            || [&level.citizens, &level.policemen, &level.commandos, &level.fish, &level.bells]
+                .iter()
+                .any(|entities| entities.contains(&tile))
+    }
+
+    fn paint(&mut self, tile: Option<(usize, usize)>) {
+        let Some((row, col)) = tile else { return };
+        let brush = BRUSHES[self.brush];
+        if self.occupied((row, col)) || self.tilemap.tiles[row][col] == brush { return }
+        self.tilemap.tiles[row][col] = brush;
+        self.changed = true;
+    }
+
+    // write the painted tiles back into the level, as an ascii map
+    fn save(&mut self) {
+        let rows = self.tilemap.tiles.len();
+        self.level.tilemap = self.tilemap.tiles[1..rows-1]
+            .iter()
+            .map(|row| row[1..row.len()-1].iter().map(|tile| tile.id()).collect())
+            .collect();
+        let path = Self::path(self.index);
+        match self.level.to_ascii().and_then(|level| loader::write_level(&level, &path)) {
+            Ok(()) => { self.changed = false },
+            Err(e) => { println!("Failed to save '{path}': {e}") }
+        }
+    }
+
+    // open another level, or go back to the menu with `MenuAction::QuitToMenu`;
+    // with unsaved changes, the menu asks first
+    fn leave(&mut self, ctx: &mut Context<'a>, action: MenuAction) -> Transition<'a> {
+        if self.changed {
+            // the question is shown over the current frame
+            let alpha = ctx.clock.alpha();
+            self.render(ctx, alpha);
+            return Transition::Push(Box::new(Menu::confirm(ctx, action)));
+        }
+        match action {
+            MenuAction::Edit(index) => Transition::Replace(Box::new(Editor::init(ctx, index))),
+            _ => Transition::Clear(Box::new(Menu::main(ctx)))
+        }
+    }
+
+    fn neighbour(&self, step: i32) -> MenuAction {
+        MenuAction::Edit((self.index as i32 + step).rem_euclid(LEVELS.len() as i32) as usize)
+    }
+
+    fn update_hud(&mut self, ctx: &Context<'a>) {
+        let brush = match BRUSHES[self.brush] {
+            TileType::Floor => "Floor",
+            TileType::Wall => "Wall",
+            TileType::Liquid => "Liquid",
+            TileType::None => "Eraser"
+        };
+        let left = vec![
+            (format!("EDITOR - {}", self.level.name.to_uppercase()), Color::WHITE),
+            (format!("Brush: {brush}"), Color::WHITE),
+            match self.changed {
+                true => ("Unsaved changes".to_string(), Color::RGB(255, 210, 90)),
+                false => ("Saved".to_string(), Color::RGB(128, 128, 128))
+            }
+        ];
+        self.hud.set_text(ctx.texture_creator, &ctx.font, &left, &[]);
+    }
+}
+
+impl<'a> Scene<'a> for Editor<'a> {
+    fn enter(&mut self, ctx: &mut Context<'a>) {
+        self.tilemap.calc_scale_translation_debug(&mut ctx.canvas, &ctx.settings);
+    }
+
+    fn resume(&mut self, ctx: &mut Context<'a>) {
+        self.tilemap.calc_scale_translation_debug(&mut ctx.canvas, &ctx.settings);
+    }
+
+    fn handle_event(&mut self, ctx: &mut Context<'a>, event: &Event, actions: &[Action]) -> Transition<'a> {
+        for &action in actions.iter() {
+            match action {
+                Action::Cancel | Action::Pause => { return self.leave(ctx, MenuAction::QuitToMenu) },
+                Action::NextBrush => { self.brush = (self.brush + 1) % BRUSHES.len() },
+                Action::CursorLeft => { return self.leave(ctx, self.neighbour(-1)) },
+                Action::CursorRight => { return self.leave(ctx, self.neighbour(1)) },
+                Action::CenterCamera => {
+                    self.tilemap.focus = None;
+                    self.tilemap.calc_scale_translation_debug(&mut ctx.canvas, &ctx.settings);
+                },
+                _ => {}
+            }
+        }
+        match *event {
+            Event::KeyDown {keycode: Some(Keycode::S), keymod, ..} if keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) => { self.save() },
+            Event::MouseButtonDown {mouse_btn: MouseButton::Left, x, y, ..} => { self.paint(self.tile_at(&ctx.canvas, x, y)) },
+            // dragging with the left button keeps painting, with the right button it pans the camera
+            Event::MouseMotion {mousestate, x, y, xrel, yrel, ..} => {
+                if mousestate.left() {
+                    self.paint(self.tile_at(&ctx.canvas, x, y));
+                }
+                if mousestate.right() {
+                    let (dx, dy) = input::to_pixels(&ctx.canvas, xrel, yrel);
+                    self.tilemap.pan(dx, dy);
+                }
+            },
+            Event::MouseWheel {y, direction, ..} => {
+                let notches = match direction {
+                    MouseWheelDirection::Flipped => -y,
+                    _ => y
+                };
+                self.tilemap.zoom_by(notches);
+                self.tilemap.calc_scale_translation_debug(&mut ctx.canvas, &ctx.settings);
+            },
+            Event::Window { win_event: WindowEvent::Resized(..), ..} => {
+                self.tilemap.calc_scale_translation_debug(&mut ctx.canvas, &ctx.settings);
+            },
+            _ => {}
+        }
+        Transition::None
+    }
+
+    fn update(&mut self, ctx: &mut Context<'a>) -> Transition<'a> {
+        self.tilemap.calc_view();
+        self.hovered = self.tile_at(&ctx.canvas, ctx.event_pump.mouse_state().x(), ctx.event_pump.mouse_state().y());
+        self.update_hud(ctx);
+        Transition::None
+    }
+
+    fn render(&mut self, ctx: &mut Context<'a>, alpha: f32) {
+        let canvas = &mut ctx.canvas;
+        self.tilemap.interpolate_view(alpha);
+        canvas.set_draw_color(Color::BLACK);
+        canvas.clear();
+
+        let mut drawables: Vec<(String, (usize, usize), Color)> = vec![];
+        for (row, tiles) in self.tilemap.tiles.iter().enumerate() {
+            for (col, tile) in tiles.iter().enumerate() {
+                match tile {
+                    TileType::Floor => { drawables.push(("floor".to_string(), (row, col), Color::WHITE)) },
+                    TileType::Wall => { drawables.push(("floor".to_string(), (row, col), WALL_COLOR)) },
+                    TileType::Liquid => { drawables.push(("liquid".to_string(), (row, col), Color::WHITE)) },
+                    TileType::None => {}
+                }
+            }
+        }
+        if let Some(tile) = self.hovered {
+            drawables.push(("highlight".to_string(), tile, Color::WHITE));
+        }
+
+        // entities in their idle pose, on top of the tiles
+        let millis = self.started.elapsed().as_millis() as u64;
+        let level = &self.level;
+        let mut entities: Vec<(String, (usize, usize))> = vec![
+            ("exit".to_string(), level.exit),
+            (self.sprites.pose("cat", Pose::Idle).frame_at(millis).to_string(), level.player)
+        ];
+        for (character, positions) in [("citizen", &level.citizens), ("police", &level.policemen), ("commando", &level.commandos)] {
+            let frame = self.sprites.pose(character, Pose::Idle).frame_at(millis).to_string();
+            entities.extend(positions.iter().map(|&pos| (frame.clone(), pos)));
+        }
+        entities.extend(level.fish.iter().map(|&pos| ("fish".to_string(), pos)));
+        entities.extend(level.bells.iter().map(|&pos| ("bell".to_string(), pos)));
+        entities.sort_by_key(|(_, pos)| *pos);
+        drawables.extend(entities.into_iter().map(|(sprite, pos)| (sprite, pos, Color::WHITE)));
+
+        // straight into the window, edges are rounded the same way for neighbouring tiles so there are no gaps
+        let (scale, translation_x, translation_y) = (self.tilemap.scale, self.tilemap.translation_x, self.tilemap.translation_y);
+        let to_screen = |x: i32, y: i32| ((x as f32 * scale).round() as i32 + translation_x, (y as f32 * scale).round() as i32 + translation_y);
+        for (sprite, (row, col), color) in drawables.iter() {
+            let (x, y) = self.tilemap.get_tile_pos(*row, *col);
+            let (offset_x, offset_y) = self.sprites.offset(sprite);
+            let (width, height) = self.sprites.size(sprite);
+            let (left, top) = to_screen(x + offset_x, y + offset_y);
+            let (right, bottom) = to_screen(x + offset_x + width as i32, y + offset_y + height as i32);
+            let rect = Rect::new(left, top, (right - left).max(1) as u32, (bottom - top).max(1) as u32);
+            self.sprites.draw(canvas, sprite, rect, false, *color);
+        }
+
+        self.hud.draw(canvas, None);
+    }
+}
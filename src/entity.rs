@@ -3,6 +3,9 @@ use crate::{map::TileType, level::State};
 extern crate queues;
 use queues::*;
 
+// part of the view distance left for seeing something on a completely dark tile
+const DARK_VIEW: f32 = 0.5;
+
 pub trait Entity {
     const SPEED: usize;
     fn get_position(&self) -> (usize, usize);
@@ -111,6 +114,12 @@ pub trait Search: Entity {
 
 pub trait Sight: Entity {
     const VIEW_DISTANCE: usize;
+    // like `sees`, but the view distance shrinks with how lit the target tile is, from 0 (dark) to 1
+    fn sees_in_light(&self, target: (usize, usize), map: &Vec<Vec<TileType>>, light: f32) -> bool {
+        let view_distance = Self::VIEW_DISTANCE as f32 * (DARK_VIEW + (1.0 - DARK_VIEW) * light.clamp(0.0, 1.0));
+        self.distance_to(target) <= view_distance && self.sees(target, map)
+    }
+
     fn sees(&self, target: (usize, usize), map: &Vec<Vec<TileType>>) -> bool {
         let (self_row, self_col) = self.get_position();
         let (other_row, other_col) = target;
@@ -0,0 +1,115 @@
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, Texture, TextureCreator, WindowCanvas};
+use sdl2::ttf::Font;
+use sdl2::video::WindowContext;
+
+// light gem colors, on a completely dark tile and on a fully lit one
+const DARK: (u8, u8, u8) = (24, 28, 40);
+const LIT: (u8, u8, u8) = (255, 236, 170);
+
+// text shown over the level, each line is rendered again only when it changes
+pub struct Hud<'a> {
+    // top left and top right corners, lines with their text and color
+    left: Vec<(String, Color, Texture<'a>)>,
+    right: Vec<(String, Color, Texture<'a>)>
+}
+
+impl<'a> Hud<'a> {
+    pub fn init() -> Self {
+        Self { left: vec![], right: vec![] }
+    }
+
+    pub fn set_text(
+        &mut self,
+        texture_creator: &'a TextureCreator<WindowContext>,
+        font: &Font,
+        left: &[(String, Color)],
+        right: &[(String, Color)]
+    ) {
+        update_lines(&mut self.left, texture_creator, font, left);
+        update_lines(&mut self.right, texture_creator, font, right);
+    }
+
+    // the light gem is only shown with the cat's visibility
+    pub fn draw(&self, canvas: &mut WindowCanvas, visibility: Option<f32>) {
+        let (canvas_x, canvas_y) = canvas.output_size().unwrap();
+        let line_height = (canvas_y as i32 / 30).max(10);
+        let margin = line_height / 2;
+
+        canvas.set_blend_mode(BlendMode::Blend);
+        for (i, (_, _, texture)) in self.left.iter().enumerate() {
+            let width = text_width(texture, line_height);
+            draw_line(canvas, texture, Rect::new(margin, margin + i as i32 * line_height, width, line_height as u32));
+        }
+        for (i, (_, _, texture)) in self.right.iter().enumerate() {
+            let width = text_width(texture, line_height);
+            let x = canvas_x as i32 - margin - width as i32;
+            draw_line(canvas, texture, Rect::new(x, margin + i as i32 * line_height, width, line_height as u32));
+        }
+        canvas.set_blend_mode(BlendMode::None);
+
+        if let Some(visibility) = visibility {
+            draw_light_gem(canvas, visibility);
+        }
+    }
+}
+
+fn update_lines<'a>(
+    lines: &mut Vec<(String, Color, Texture<'a>)>,
+    texture_creator: &'a TextureCreator<WindowContext>,
+    font: &Font,
+    text: &[(String, Color)]
+) {
+    lines.truncate(text.len());
+    for (i, (text, color)) in text.iter().enumerate() {
+        if let Some((old_text, old_color, _)) = lines.get(i) {
+            if old_text == text && old_color == color { continue }
+        }
+        let surface = font
+            .render(text)
+            .blended(*color)
+            .map_err(|e| e.to_string())
+            .unwrap();
+        let texture = texture_creator.create_texture_from_surface(&surface).map_err(|e| e.to_string()).unwrap();
+        match i < lines.len() {
+            true => { lines[i] = (text.clone(), *color, texture) },
+            false => { lines.push((text.clone(), *color, texture)) }
+        }
+    }
+}
+
+fn text_width(texture: &Texture, height: i32) -> u32 {
+    let query = texture.query();
+    (query.width as i32 * height / query.height.max(1) as i32) as u32
+}
+
+// text on a dark band, so it can be read over lit tiles
+fn draw_line(canvas: &mut WindowCanvas, texture: &Texture, rect: Rect) {
+    canvas.set_draw_color(Color::RGBA(0, 0, 0, 160));
+    canvas.fill_rect(Rect::new(rect.x() - 4, rect.y(), rect.width() + 8, rect.height())).unwrap();
+    canvas.copy(texture, None, rect).unwrap();
+}
+
+// how visible the cat is to robots, like the light gem of stealth games:
+// a diamond in the bottom left corner, from dark to bright
+fn draw_light_gem(canvas: &mut WindowCanvas, visibility: f32) {
+    let (_, canvas_y) = canvas.output_size().unwrap();
+    // half the height of the diamond, it's twice as wide as it's tall like the tiles
+    let size = (canvas_y as i32 / 32).max(6);
+    let (center_x, center_y) = (size * 3, canvas_y as i32 - size * 2);
+
+    let mix = |dark: u8, lit: u8| (dark as f32 + (lit as f32 - dark as f32) * visibility.clamp(0.0, 1.0)) as u8;
+    let fill = Color::RGB(mix(DARK.0, LIT.0), mix(DARK.1, LIT.1), mix(DARK.2, LIT.2));
+
+    // one row at a time, with a border one pixel wider
+    let rows = |size: i32| (-size..=size).map(move |dy| {
+        let half_width = (size - dy.abs()) * 2;
+        Rect::new(center_x - half_width, center_y + dy, (half_width * 2).max(1) as u32, 1)
+    });
+    canvas.set_draw_color(Color::RGB(96, 96, 96));
+    canvas.fill_rects(&rows(size + 1).collect::<Vec<Rect>>()).unwrap();
+    canvas.set_draw_color(fill);
+    canvas.fill_rects(&rows(size).collect::<Vec<Rect>>()).unwrap();
+    canvas.set_draw_color(Color::BLACK);
+}
@@ -0,0 +1,195 @@
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::mouse::MouseButton;
+use sdl2::render::WindowCanvas;
+
+use crate::controller::Controllers;
+
+// everything the player can do, independent of the device used to do it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Action {
+    Confirm,
+    Cancel,
+    Wait,
+    Undo,
+    Pause,
+    CursorUp,
+    CursorDown,
+    CursorLeft,
+    CursorRight,
+    CycleRobots,
+    CenterCamera,
+    NextBrush,
+    ToggleFullscreen,
+    Mute,
+    VolumeUp,
+    VolumeDown
+}
+
+pub const ACTIONS: [Action; 16] = [
+    Action::Confirm, Action::Cancel, Action::Wait, Action::Undo, Action::Pause,
+    Action::CursorUp, Action::CursorDown, Action::CursorLeft, Action::CursorRight,
+    Action::CycleRobots, Action::CenterCamera, Action::NextBrush, Action::ToggleFullscreen, Action::Mute, Action::VolumeUp, Action::VolumeDown
+];
+
+impl Action {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Confirm => "Confirm",
+            Action::Cancel => "Cancel",
+            Action::Wait => "Wait",
+            Action::Undo => "Undo",
+            Action::Pause => "Pause",
+            Action::CursorUp => "Cursor up",
+            Action::CursorDown => "Cursor down",
+            Action::CursorLeft => "Cursor left",
+            Action::CursorRight => "Cursor right",
+            Action::CycleRobots => "Look at robots",
+            Action::CenterCamera => "Center on cat",
+            Action::NextBrush => "Next brush (editor)",
+            Action::ToggleFullscreen => "Fullscreen",
+            Action::Mute => "Mute",
+            Action::VolumeUp => "Volume up",
+            Action::VolumeDown => "Volume down",
+        }
+    }
+}
+
+// a single key or button, stored by its SDL name, e.g. {"key": "Return"} or {"pad": "a"}
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Binding {
+    Key(String),
+    Mouse(String),
+    Pad(String)
+}
+
+impl Binding {
+    // the binding an event would trigger, if it's a press of some kind
+    pub fn from_event(event: &Event) -> Option<Self> {
+        match event {
+            Event::KeyDown { keycode: Some(keycode), .. } => Some(Binding::Key(keycode.name())),
+            Event::MouseButtonDown { mouse_btn, .. } => {
+                let name = match mouse_btn {
+                    MouseButton::Left => "left",
+                    MouseButton::Middle => "middle",
+                    MouseButton::Right => "right",
+                    MouseButton::X1 => "x1",
+                    MouseButton::X2 => "x2",
+                    MouseButton::Unknown => return None
+                };
+                Some(Binding::Mouse(name.to_string()))
+            },
+            Event::ControllerButtonDown { button, .. } => Some(Binding::Pad(button.string())),
+            _ => None
+        }
+    }
+
+    fn same_device(&self, other: &Binding) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Binding::Key(name) => name.clone(),
+            Binding::Mouse(name) => format!("Mouse {name}"),
+            Binding::Pad(name) => format!("Pad {name}")
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bindings(BTreeMap<Action, Vec<Binding>>);
+
+impl Default for Bindings {
+    fn default() -> Self {
+        let key = |keycode: Keycode| Binding::Key(keycode.name());
+        let mouse = |name: &str| Binding::Mouse(name.to_string());
+        let pad = |name: &str| Binding::Pad(name.to_string());
+        Self(BTreeMap::from([
+            (Action::Confirm, vec![key(Keycode::Return), key(Keycode::KpEnter), mouse("left"), pad("a")]),
+            (Action::Cancel, vec![key(Keycode::Escape), pad("b")]),
+            (Action::Wait, vec![key(Keycode::Space), pad("b")]),
+            (Action::Undo, vec![key(Keycode::Z), key(Keycode::Backspace), pad("back")]),
+            (Action::Pause, vec![key(Keycode::Escape), pad("start")]),
+            (Action::CursorUp, vec![key(Keycode::Up), key(Keycode::W), pad("dpup")]),
+            (Action::CursorDown, vec![key(Keycode::Down), key(Keycode::S), pad("dpdown")]),
+            (Action::CursorLeft, vec![key(Keycode::Left), key(Keycode::A), pad("dpleft")]),
+            (Action::CursorRight, vec![key(Keycode::Right), key(Keycode::D), pad("dpright")]),
+            (Action::CycleRobots, vec![key(Keycode::Tab), pad("y")]),
+            (Action::CenterCamera, vec![key(Keycode::C), pad("rightstick")]),
+            (Action::NextBrush, vec![key(Keycode::B), pad("x")]),
+            (Action::ToggleFullscreen, vec![key(Keycode::F11)]),
+            (Action::Mute, vec![key(Keycode::M)]),
+            (Action::VolumeUp, vec![key(Keycode::Equals), key(Keycode::KpPlus)]),
+            (Action::VolumeDown, vec![key(Keycode::Minus), key(Keycode::KpMinus)]),
+        ]))
+    }
+}
+
+impl Bindings {
+    // add default bindings for actions missing from an older settings file
+    pub fn fill_defaults(&mut self) {
+        for (action, bindings) in Bindings::default().0 {
+            self.0.entry(action).or_insert(bindings);
+        }
+    }
+
+    pub fn get(&self, action: Action) -> &[Binding] {
+        self.0.get(&action).map(|b| b.as_slice()).unwrap_or(&[])
+    }
+
+    // bind an action to a new key or button, replacing its old binding on the same device;
+    // a key or button another action already uses is refused, returning that action
+    pub fn rebind(&mut self, action: Action, binding: Binding) -> Result<(), Action> {
+        let taken = self.0
+            .iter()
+            .find(|(&other, bindings)| other != action && bindings.contains(&binding))
+            .map(|(&other, _)| other);
+        // defaults share some keys, e.g. Escape for Cancel and Pause, those can be kept
+        if let Some(other) = taken.filter(|_| !self.get(action).contains(&binding)) {
+            return Err(other);
+        }
+        let bindings = self.0.entry(action).or_default();
+        bindings.retain(|b| !b.same_device(&binding));
+        bindings.push(binding);
+        Ok(())
+    }
+
+    pub fn actions(&self, event: &Event) -> Vec<Action> {
+        let Some(binding) = Binding::from_event(event) else { return vec![] };
+        self.0
+            .iter()
+            .filter(|(_, bindings)| bindings.contains(&binding))
+            .map(|(&action, _)| action)
+            .collect()
+    }
+}
+
+// input state shared by all screens: connected controllers and the current bindings
+pub struct Input {
+    pub controllers: Controllers,
+    pub bindings: Bindings
+}
+
+impl Input {
+    pub fn init(controllers: Controllers, bindings: Bindings) -> Self {
+        Self { controllers, bindings }
+    }
+
+    // keep track of controllers, and translate the event into the actions bound to it
+    pub fn handle_event(&mut self, event: &Event) -> Vec<Action> {
+        self.controllers.handle_event(event);
+        self.bindings.actions(event)
+    }
+}
+
+// mouse positions and movements are in window coordinates, drawing is in pixels,
+// which on HiDPI displays there are more of
+pub fn to_pixels(canvas: &WindowCanvas, x: i32, y: i32) -> (i32, i32) {
+    let (window_x, window_y) = canvas.window().size();
+    let (canvas_x, canvas_y) = canvas.output_size().unwrap();
+    (x * canvas_x as i32 / window_x.max(1) as i32, y * canvas_y as i32 / window_y.max(1) as i32)
+}
@@ -1,37 +1,41 @@
-use sdl2::EventPump;
 use sdl2::pixels::Color;
-use sdl2::render::{ WindowCanvas, TextureCreator, Texture};
-use sdl2::image::LoadTexture;
-use sdl2::ttf::Font;
-use sdl2::video::{WindowContext, FullscreenType};
+use sdl2::render::WindowCanvas;
 use sdl2::rect::Rect;
 use sdl2::event::{Event, WindowEvent};
-use sdl2::keyboard::Keycode;
-use sdl2::mouse::{Cursor, SystemCursor, MouseButton};
-use std::collections::HashMap;
-
-use crate::mixer::Mixer;
-use crate::splash::{show_splash, SplashResult};
-use crate::{loader, DEBUG};
+use sdl2::keyboard::{Keycode, Mod};
+use sdl2::mouse::{Cursor, SystemCursor, MouseButton, MouseWheelDirection};
+use std::time::Duration;
+
+use crate::menu::Menu;
+use crate::input::{self, Action};
+use crate::scene::{Context, Scene, SceneBox, Transition};
+use crate::splash::Splash;
+use crate::summary::Summary;
+use crate::collectible::{Collectible, CollectibleKind};
+use crate::objective::Objective;
+use crate::scores::{LevelStats, Scores};
+use crate::threat::{self, Threat};
+use crate::loader;
+use crate::settings::Settings;
 use crate::animation::Animation;
+use crate::sprites::{Pose, Sprites, SpriteAnimation};
+use crate::view::View;
+use crate::light::{self, LightMap};
+use crate::clock::TICK;
 use crate::map::{Map, TileType};
 use crate::entity::{Entity, Search, Sight};
 use crate::player::Player;
 use crate::robots::citizen::*;
 use crate::robots::policeman::*;
 use crate::robots::commando::*;
+use crate::LEVELS;
 
 extern crate sdl2;
 
-const FRAME_DURATION: u64 = 50;
-
-#[derive(PartialEq)]
-pub enum GameResult {
-    Quit,
-    Menu,
-    Victory,
-    Defeat
-}
+// used when a level doesn't pick its own music
+const DEFAULT_MUSIC: [&str; 2] = ["slow", "fast"];
+// how many ticks the cat's caught or victory pose is shown for before the level ends (800ms)
+const POSE_TICKS: u32 = 40;
 
 #[derive(Clone)]
 pub struct State {
@@ -41,10 +45,17 @@ pub struct State {
     pub citizens: Vec<Citizen>,
     pub policemen: Vec<Policeman>,
     pub commandos: Vec<Commando>,
-    pub seen_timer: i32,
-    pub animation: Option<Animation>,
+    pub collectibles: Vec<Collectible>,
+    pub objectives: Vec<Objective>,
+    pub lights: LightMap,
+    pub turns: u32,
+    pub times_detected: u32,
+    // clock ticks since the level started, for looping animations
+    pub ticks: u32,
     pub trail: Vec<(usize, usize)>,
-    pub move_to: (usize, usize)
+    pub move_to: (usize, usize),
+    pub cursor: (usize, usize),
+    pub keyboard_cursor: bool
 }
 
 impl State {
@@ -64,6 +75,12 @@ impl State {
             .iter()
             .map(|&c| Commando::init(c))
             .collect();
+        let collectibles: Vec<Collectible> = level.fish
+            .iter()
+            .map(|&pos| Collectible::init(pos, CollectibleKind::Fish))
+            .chain(level.bells.iter().map(|&pos| Collectible::init(pos, CollectibleKind::Bell)))
+            .collect();
+        let lights = LightMap::init(&level.lights, &tilemap, level.exit);
         Self {
             tilemap: tilemap,
             player: player,
@@ -71,10 +88,16 @@ impl State {
             citizens: citizens,
             policemen: policemen,
             commandos: commandos,
-            seen_timer: 0,
-            animation: None,
+            collectibles,
+            objectives: level.objectives,
+            lights,
+            turns: 0,
+            times_detected: 0,
+            ticks: 0,
             trail: vec![],
-            move_to: (0, 0)
+            move_to: (0, 0),
+            cursor: level.player,
+            keyboard_cursor: false
         }
     }
 
@@ -87,215 +110,555 @@ impl State {
         }
         self.tilemap.passable(tile)
     }
+
+    // move the keyboard cursor by one tile, staying inside the map
+    pub fn move_cursor(&mut self, (drow, dcol): (isize, isize)) {
+        if !self.keyboard_cursor {
+            self.keyboard_cursor = true;
+            self.cursor = self.player.get_position();
+        }
+        let row = (self.cursor.0 as isize + drow).clamp(0, self.tilemap.tiles.len() as isize - 1) as usize;
+        let col = (self.cursor.1 as isize + dcol).clamp(0, self.tilemap.tiles[row].len() as isize - 1) as usize;
+        self.cursor = (row, col);
+    }
+
+    // robots that can see the cat right now
+    pub fn watchers(&self) -> usize {
+        let player = self.player.get_position();
+        let (tiles, light) = (&self.tilemap.tiles, self.lights.visibility(player));
+        self.citizens.iter().filter(|c| c.sees_in_light(player, tiles, light)).count()
+            + self.policemen.iter().filter(|p| p.sees_in_light(player, tiles, light)).count()
+            + self.commandos.iter().filter(|c| c.sees_in_light(player, tiles, light)).count()
+    }
+
+    // jump the keyboard cursor to the next robot the cat can see, closest first
+    pub fn cycle_cursor(&mut self) {
+        let mut visible: Vec<(usize, usize)> = self.citizens.iter().map(|c| c.get_position())
+            .chain(self.policemen.iter().map(|p| p.get_position()))
+            .chain(self.commandos.iter().map(|c| c.get_position()))
+            .filter(|&pos| self.player.sees(pos, &self.tilemap.tiles))
+            .collect();
+        if visible.is_empty() { return; }
+        visible.sort_by(|a, b| self.player.distance_to(*a).total_cmp(&self.player.distance_to(*b)));
+        let next = match visible.iter().position(|&pos| pos == self.cursor) {
+            Some(i) if self.keyboard_cursor => (i + 1) % visible.len(),
+            _ => 0
+        };
+        self.keyboard_cursor = true;
+        self.cursor = visible[next];
+    }
 }
 
-pub fn play_level(
-    canvas: &mut WindowCanvas, 
-    texture_creator: &TextureCreator<WindowContext>,
-    event_pump: &mut EventPump,
-    font: &mut Font,
-    music_mixer: &mut Mixer,
-    name: &str
-) -> GameResult {
+// a level being played, the scene after its name splash
+pub struct Level<'a> {
+    index: usize,
+    // deaths on this level so far, reset once it's beaten
+    deaths: u32,
+    title: String,
+    state: State,
+    view: View<'a>,
+    music: Vec<String>,
+    threat: Threat,
+    // time spent in the level, the pause menu doesn't count
+    played: Duration,
+    // input waiting for the next tick
+    take_turn: bool,
+    phase: Phase,
+    debug: bool,
+    _cursor: Cursor
+}
 
-    let cursor = Cursor::from_system(SystemCursor::Crosshair).unwrap();
-    cursor.set();
+// show the level's name, then play it
+pub fn start<'a>(ctx: &mut Context<'a>, index: usize, deaths: u32) -> SceneBox<'a> {
+    let level = Level::init(ctx, index, deaths);
+    let title = level.title.to_uppercase();
+    Box::new(Splash::init(ctx, title, 0.75, 3000, Some(Box::new(level))))
+}
 
-    music_mixer.play_song("slow");
+impl<'a> Level<'a> {
+    pub fn init(ctx: &mut Context<'a>, index: usize, deaths: u32) -> Self {
+        let debug = ctx.settings.debug;
+        let texture_creator = ctx.texture_creator;
+
+        let cursor = Cursor::from_system(SystemCursor::Crosshair).unwrap();
+        cursor.set();
+
+        // load level data and initialize game state
+        let level = loader::load_level("resources/levels/".to_string() + LEVELS[index] + ".json");
+        let level_name = level.name.clone();
+        let music: Vec<String> = match level.music.is_empty() {
+            true => DEFAULT_MUSIC.iter().map(|track| track.to_string()).collect(),
+            false => level.music.clone()
+        };
+        let mut state: State = State::init(level);
+        state.move_to = state.player.get_position();
+        state.trail = vec![];
+
+        if debug {
+            state.tilemap.print();
+        }
 
-    // load level data and initialize game state
-    let level = loader::load_level("resources/levels/".to_string() + name + ".json");
-    let level_name = level.name.clone();
-    let mut state: State = State::init(level);
-    state.move_to = state.player.get_position();
-    state.trail = vec![];
+        // levels can have their own look for tiles
+        let sprites = Sprites::load(texture_creator, Some(&level_name));
 
-    match show_splash(canvas, &texture_creator, event_pump, font, level_name.to_string().to_uppercase(), 0.75, 3000) {
-        SplashResult::Ok => {},
-        SplashResult::Quit => { return GameResult::Quit; }
+        Self {
+            index,
+            deaths,
+            title: level_name,
+            state,
+            view: View::init(texture_creator, sprites),
+            music,
+            threat: Threat::init(),
+            played: Duration::ZERO,
+            take_turn: false,
+            phase: Phase::Input,
+            debug,
+            _cursor: cursor
+        }
     }
 
-    if DEBUG {
-        state.tilemap.print();
+    fn calc_scale_translation(&mut self, canvas: &mut WindowCanvas, settings: &Settings) {
+        match self.debug {
+            false => { self.state.tilemap.calc_scale_translation(canvas, self.state.player.get_position(), settings); },
+            true => { self.state.tilemap.calc_scale_translation_debug(canvas, settings); }
+        }
     }
 
-    match DEBUG {
-        false => { state.tilemap.calc_scale_translation(canvas, state.player.get_position()); },
-        true => { state.tilemap.calc_scale_translation_debug(canvas); }
+    // tile under the mouse pointer
+    fn tile_at(&self, canvas: &WindowCanvas, x: i32, y: i32) -> (usize, usize) {
+        let (x, y) = input::to_pixels(canvas, x, y);
+        self.state.tilemap.get_tile_index(
+            ((x - self.state.tilemap.translation_x) as f32 / self.state.tilemap.scale) as i32, 
+            ((y - self.state.tilemap.translation_y) as f32 / self.state.tilemap.scale) as i32
+        )
     }
-    
-    // load all sprites
-    let mut sprites: HashMap<String, Texture> = HashMap::new();
-    let common_textures = vec![
-        "highlight", "citizen_calm", "citizen_alert", "police_calm", "police_alert", "commando_calm", "commando_alert", 
-        "cat_idle_1", "cat_run_0", "cat_run_1", "cat_run_2", "cat_run_3", "cat_run_4"
-    ];
-    for name in common_textures.iter() { 
-        sprites.insert(name.to_string(), texture_creator.load_texture(format!("resources/images/{}.png", *name)).unwrap()); 
-    }
-    let level_textures = vec![
-        "floor", "liquid", "wall_left", "wall_right", "wall_left_transparent", "wall_right_transparent",
-        "border_left", "border_right", "border_corner", "exit"
-    ];
-    for name in level_textures.iter() { 
-        sprites.insert(
-            name.to_string(), 
-            texture_creator.load_texture(format!("resources/images/{}/{}.png", level_name, *name))
-            .unwrap_or(texture_creator.load_texture(format!("resources/images/{}.png", *name)).unwrap())
-        ); 
-    }
-    sprites.get_mut("highlight").unwrap().set_alpha_mod(128);
-
-
-    loop {
-
-        if state.seen_timer != 0 { music_mixer.play_song("fast"); }
-        else { music_mixer.play_song("slow"); }
-
-        // get mouse position and determine selected tile
-        let (mouse_x, mouse_y) = (
-            event_pump.mouse_state().x(), 
-            event_pump.mouse_state().y()
-        );
-        let (row, col) = state.tilemap.get_tile_index(
-            (mouse_x - state.tilemap.translation_x) / state.tilemap.scale as i32, 
-            (mouse_y - state.tilemap.translation_y) / state.tilemap.scale as i32
-        );
-
-        // if new tile selected (and no animation is underway), recalculate path
-        if state.move_to != (row, col) && state.animation.is_none() {
-            state.move_to = (row, col); 
-            state.trail = state.player.find_shortest_path(state.move_to, &state);
-            
-            if state.trail.len() > 5 {
-                state.trail = (&state.trail[0..5]).to_vec();
+
+    // turn, alert and how many robots see the cat on the left, objectives on the right
+    fn update_hud(&mut self, ctx: &Context<'a>) {
+        let state = &self.state;
+        let alert = match self.threat.turns_left() {
+            Some(turns) => format!("{} ({turns} turns)", self.threat.name()),
+            None => self.threat.name().to_string()
+        };
+        let alert_color = match self.threat.level {
+            0 => Color::WHITE,
+            1 => Color::RGB(255, 210, 90),
+            _ => Color::RGB(255, 90, 80)
+        };
+        let watchers = state.watchers();
+        let left = vec![
+            (self.title.to_uppercase(), Color::WHITE),
+            (format!("Turn {}", state.turns), Color::WHITE),
+            (alert, alert_color),
+            (format!("Seen by {watchers}"), if watchers == 0 { Color::RGB(128, 128, 128) } else { Color::RGB(255, 90, 80) })
+        ];
+
+        // same colors as the level-complete screen
+        let status = |achieved: bool| if achieved { Color::RGB(153, 229, 80) } else { Color::RGB(128, 128, 128) };
+        let mut right = vec![];
+        if !state.collectibles.is_empty() {
+            let collected = state.collectibles.iter().filter(|c| c.collected).count();
+            right.push((format!("Collected {}/{}", collected, state.collectibles.len()), status(collected == state.collectibles.len())));
+        }
+        for objective in state.objectives.iter() {
+            right.push((objective.description(), status(objective.completed(state))));
+        }
+
+        self.view.hud.set_text(ctx.texture_creator, &ctx.font, &left, &right);
+    }
+
+    fn defeat(&mut self, ctx: &mut Context<'a>) -> Transition<'a> {
+        let next = start(ctx, self.index, self.deaths + 1);
+        Transition::Replace(Box::new(Splash::init(ctx, "You Died".to_string(), 0.75, 4500, Some(next))))
+    }
+
+    // what comes after the level is beaten: the next level, or back to the menu after the last one
+    fn escaped(&mut self, ctx: &mut Context<'a>) -> SceneBox<'a> {
+        match self.index + 1 < LEVELS.len() {
+            true => {
+                let next = start(ctx, self.index + 1, 0);
+                Box::new(Splash::init(ctx, "You escaped!".to_string(), 0.75, 2500, Some(next)))
+            },
+            false => {
+                let next = Box::new(Menu::main(ctx));
+                Box::new(Splash::init(ctx, "Congratulations you won!".to_string(), 0.75, 4500, Some(next)))
             }
         }
+    }
 
-        // player move and/or handle events
-        for event in event_pump.poll_iter() {
-            match event {
-                Event::Quit {..} => { return GameResult::Quit },
-                Event::KeyDown { keycode: Some(Keycode::F11), ..} => {
-                    match canvas.window().fullscreen_state() {
-                        FullscreenType::Off => { canvas.window_mut().set_fullscreen(FullscreenType::True).unwrap() },
-                        _ => { canvas.window_mut().set_fullscreen(FullscreenType::Off).unwrap() }
-                    }
+    fn complete(&mut self, ctx: &mut Context<'a>) -> Transition<'a> {
+        let state = &self.state;
+        let stats = LevelStats {
+            turns: state.turns,
+            times_detected: state.times_detected,
+            deaths: self.deaths,
+            seconds: self.played.as_secs() as u32,
+            collected: state.collectibles.iter().filter(|c| c.collected).count() as u32
+        };
+
+        ctx.music_mixer.stop_music();
+        ctx.music_mixer.play_effect("win");
+
+        let mut scores = Scores::load();
+        let new_best = scores.submit(LEVELS[self.index], stats);
+        scores.save();
+
+        let mut lines: Vec<(String, Option<bool>)> = vec![];
+        if !state.collectibles.is_empty() {
+            let collected = stats.collected as usize;
+            lines.push((format!("Collected {}/{}", collected, state.collectibles.len()), Some(collected == state.collectibles.len())));
+        }
+        for objective in state.objectives.iter() {
+            lines.push((objective.description(), Some(objective.completed(state))));
+        }
+        lines.push((format!("Turns {}   Detected {}   Deaths {}", stats.turns, stats.times_detected, stats.deaths), None));
+        lines.push((format!("Time {}:{:02}", stats.seconds / 60, stats.seconds % 60), None));
+        lines.push((format!("Score {}   {}", stats.score(), stats.stars_text()), None));
+        if new_best {
+            lines.push(("New best!".to_string(), None));
+        }
+
+        let next = self.escaped(ctx);
+        Transition::Replace(Box::new(Summary::init(ctx, "Level complete".to_string(), lines, next)))
+    }
+}
+
+impl<'a> Scene<'a> for Level<'a> {
+    fn enter(&mut self, ctx: &mut Context<'a>) {
+        ctx.music_mixer.play_song(self.threat.track(&self.music));
+        self.calc_scale_translation(&mut ctx.canvas, &ctx.settings);
+    }
+
+    fn resume(&mut self, ctx: &mut Context<'a>) {
+        // the window may have been resized from the options
+        self.calc_scale_translation(&mut ctx.canvas, &ctx.settings);
+    }
+
+    fn handle_event(&mut self, ctx: &mut Context<'a>, event: &Event, actions: &[Action]) -> Transition<'a> {
+        let state = &mut self.state;
+        for &action in actions.iter() {
+            match action {
+                // everything stays frozen while the pause menu is open
+                Action::Pause => {
+                    draw(&mut ctx.canvas, &mut self.view, state, self.debug, ctx.clock.alpha());
+                    return Transition::Push(Box::new(Menu::pause(ctx, self.index, self.deaths)));
                 },
-                Event::KeyDown {keycode: Some(Keycode::Escape), ..} => { return GameResult::Menu },
-                Event::MouseButtonDown { mouse_btn: MouseButton::Left, ..} => {
-                    match play_turn(canvas, &mut sprites, &mut state) {
-                        TurnResult::Caught => { 
-                            return GameResult::Defeat 
-                        },
-                        TurnResult::Detected => {
-                            state.seen_timer = 7500;
-                        },
-                        TurnResult::OK => {
-                            if state.player.pos == state.exit { return GameResult::Victory }
-                        },
-                    }
+                Action::Confirm => { self.take_turn = true },
+                Action::Wait => {
+                    state.trail = vec![];
+                    self.take_turn = true;
                 },
-                // shortcuts to win/lose the game instantly in debug mode
-                Event::KeyDown {keycode: Some(Keycode::W), ..} => { if DEBUG { return GameResult::Victory } }, 
-                Event::KeyDown {keycode: Some(Keycode::L), ..} => { if DEBUG { return GameResult::Defeat } },
-                Event::MouseButtonDown {mouse_btn: MouseButton::Right, ..} => { if DEBUG { println!("Clicked {:?}", (row, col)) } },
-                Event::Window { win_event: WindowEvent::Resized(..), ..} => {
-                    match DEBUG {
-                        false => { state.tilemap.calc_scale_translation(canvas, state.player.get_position()); },
-                        true => { state.tilemap.calc_scale_translation_debug(canvas); }
-                    }
+                // move the cursor along the isometric axes
+                Action::CursorUp => { state.move_cursor((-1, 0)) },
+                Action::CursorDown => { state.move_cursor((1, 0)) },
+                Action::CursorLeft => { state.move_cursor((0, -1)) },
+                Action::CursorRight => { state.move_cursor((0, 1)) },
+                Action::CycleRobots => { state.cycle_cursor() },
+                Action::CenterCamera => {
+                    state.tilemap.focus = None;
+                    self.calc_scale_translation(&mut ctx.canvas, &ctx.settings);
+                    return Transition::None;
                 },
                 _ => {}
             }
         }
+        match *event {
+            // shortcuts to win/lose the game instantly in debug mode
+            Event::KeyDown {keycode: Some(Keycode::W), keymod, ..} if self.debug && keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) => {
+                return Transition::Replace(self.escaped(ctx))
+            },
+            Event::KeyDown {keycode: Some(Keycode::L), keymod, ..} if self.debug && keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) => {
+                return self.defeat(ctx)
+            },
+            // dragging with the right button pans the camera
+            Event::MouseMotion {mousestate, xrel, yrel, ..} => {
+                state.keyboard_cursor = false;
+                if mousestate.right() {
+                    let (dx, dy) = input::to_pixels(&ctx.canvas, xrel, yrel);
+                    state.tilemap.pan(dx, dy);
+                }
+            },
+            Event::MouseWheel {y, direction, ..} => {
+                let notches = match direction {
+                    MouseWheelDirection::Flipped => -y,
+                    _ => y
+                };
+                state.tilemap.zoom_by(notches);
+                self.calc_scale_translation(&mut ctx.canvas, &ctx.settings);
+            },
+            Event::ControllerAxisMotion { axis, value, ..} => {
+                if let Some(step) = ctx.input.controllers.stick_step(axis, value) { state.move_cursor(step) }
+            },
+            Event::MouseButtonDown {mouse_btn: MouseButton::Right, x, y, ..} if self.debug => { println!("Clicked {:?}", self.tile_at(&ctx.canvas, x, y)) },
+            Event::Window { win_event: WindowEvent::Resized(..), ..} => { self.calc_scale_translation(&mut ctx.canvas, &ctx.settings) },
+            _ => {}
+        }
+        Transition::None
+    }
 
-        render(canvas, &mut sprites, &mut state);
+    fn update(&mut self, ctx: &mut Context<'a>) -> Transition<'a> {
+        self.played += TICK;
+        tick(&mut self.state);
+
+        // get mouse position and determine selected tile, unless the keyboard is steering the cursor
+        let (row, col) = self.tile_at(&ctx.canvas, ctx.event_pump.mouse_state().x(), ctx.event_pump.mouse_state().y());
+        let state = &mut self.state;
+        if !state.keyboard_cursor {
+            state.cursor = (row, col);
+        }
+
+        // if new tile selected (and the level is waiting for a turn), recalculate path
+        if state.move_to != state.cursor && self.phase == Phase::Input {
+            state.move_to = state.cursor; 
+            state.trail = state.player.find_shortest_path(state.move_to, state);
+            
+            if state.trail.len() > 5 {
+                state.trail = (&state.trail[0..5]).to_vec();
+            }
+        }
+
+        let take_turn = self.take_turn;
+        self.take_turn = false;
+
+        // player move
+        if take_turn && self.phase == Phase::Input {
+            return self.start_turn(ctx);
+        }
+        self.advance_turn(ctx)
     }
-} 
 
-#[derive(PartialEq)]
+    fn render(&mut self, ctx: &mut Context<'a>, alpha: f32) {
+        self.update_hud(ctx);
+        draw(&mut ctx.canvas, &mut self.view, &mut self.state, self.debug, alpha);
+    }
+}
+
+#[derive(PartialEq, Clone, Copy)]
 pub enum TurnResult {
     Caught,
     Detected,
     OK
 }
 
-fn play_turn(canvas: &mut WindowCanvas, sprites: &mut HashMap<String, Texture>, state: &mut State) -> TurnResult {
-    let mut seen = false;
-
-    // player turn
-    let mut points = vec![state.player.pos];
-    points.append(&mut state.trail);
-    if points.len() != 1 {
-        state.animation = Some(Animation::init(
-        points.iter().map(|(row, col)| state.tilemap.get_tile_pos(*row, *col)).collect(), 
-        vec!["cat_run_0", "cat_run_1", "cat_run_2", "cat_run_3", "cat_run_4"].iter().map(|name| name.to_string()).collect(),
-        3
-        ));
-        while state.animation.is_some() {
-            match DEBUG {
-                false => { state.tilemap.calc_translation(canvas, state.player.get_position()); },
-                true => { state.tilemap.calc_translation_debug(canvas); }
+// where the level is in a turn, the cat and the robots move over many ticks
+#[derive(PartialEq)]
+enum Phase {
+    // waiting for the player to take a turn
+    Input,
+    // the cat walks along its trail, a footstep is heard on every new tile
+    Walking { last_tile: (usize, usize) },
+    // robots the cat can see walk to where they decided to go, their sounds are heard once they get there
+    Robots { effects: Vec<(&'static str, (f32, f32))>, caught: bool, seen: bool },
+    // the cat's caught or victory pose is shown for some ticks before the turn ends
+    Pose { ticks: u32, result: TurnResult }
+}
+
+impl<'a> Level<'a> {
+    // the cat sets off along its trail, or waits where it is
+    fn start_turn(&mut self, ctx: &mut Context<'a>) -> Transition<'a> {
+        let state = &mut self.state;
+        // the camera follows the cat again once it moves
+        state.tilemap.focus = None;
+        state.turns += 1;
+
+        let mut points = vec![state.player.pos];
+        points.append(&mut state.trail);
+        for collectible in state.collectibles.iter_mut() {
+            if points.contains(&collectible.pos) {
+                collectible.collected = true;
             }
-            render(canvas, sprites, state);
         }
-    }
-    if state.player.get_position() == state.exit {
-        return TurnResult::OK;
+        if points.len() == 1 {
+            return self.walked(ctx);
+        }
+        state.player.animation = walk(state, points[0], &points[1..], self.view.sprites.pose("cat", Pose::Walk), &ctx.settings);
+        self.phase = Phase::Walking { last_tile: state.player.get_position() };
+        Transition::None
     }
 
-    // citizens turn
-    println!("Citizens turn...");
-    for i in 0..state.citizens.len() {
-        let state_copy = state.clone();
-        for tile in state.citizens[i].turn(&state_copy).iter() {
-            state.citizens[i].set_position(*tile);
-            if state.player.sees(*tile, &state.tilemap.tiles) {
-                render(canvas, sprites, state);
+    // move the turn on by one tick, the animations themselves are advanced by `tick`
+    fn advance_turn(&mut self, ctx: &mut Context<'a>) -> Transition<'a> {
+        match std::mem::replace(&mut self.phase, Phase::Input) {
+            Phase::Input => Transition::None,
+            Phase::Walking { last_tile } => {
+                let state = &mut self.state;
+                match self.debug {
+                    false => { state.tilemap.calc_translation(state.player.get_position()); },
+                    true => { state.tilemap.calc_translation_debug(); }
+                }
+                // footstep whenever the cat enters a new tile, a splash when it jumps into liquid
+                let (row, col) = state.player.get_position();
+                if (row, col) != last_tile {
+                    let last = state.tilemap.tiles[last_tile.0][last_tile.1];
+                    match state.tilemap.tiles[row][col] {
+                        TileType::Liquid if last != TileType::Liquid => { ctx.music_mixer.play_effect("splash") },
+                        TileType::Liquid => { ctx.music_mixer.play_effect("step_liquid") },
+                        _ => { ctx.music_mixer.play_effect("step_floor") }
+                    }
+                }
+                if state.player.animation.is_some() {
+                    self.phase = Phase::Walking { last_tile: (row, col) };
+                    return Transition::None;
+                }
+                self.walked(ctx)
+            },
+            Phase::Robots { effects, caught, seen } => {
+                if robots_moving(&self.state) {
+                    self.phase = Phase::Robots { effects, caught, seen };
+                    return Transition::None;
+                }
+                self.robots_arrived(ctx, effects, caught, seen)
+            },
+            Phase::Pose { ticks, result } => {
+                if ticks > 0 {
+                    self.phase = Phase::Pose { ticks: ticks - 1, result };
+                    return Transition::None;
+                }
+                self.end_turn(ctx, result)
             }
         }
-        seen = seen || state.citizens[i].sees(state.player.get_position(), &state.tilemap.tiles);
     }
-    
-    // policemen turn
-    println!("Policemen turn...");
-    for i in 0..state.policemen.len() {
-        let state_copy = state.clone();
-        for tile in state.policemen[i].turn(&state_copy).iter() {
-            state.policemen[i].set_position(*tile);
-            if state.player.sees(*tile, &state.tilemap.tiles) {
-                render(canvas, sprites, state);
-            }
+
+    // the cat got to the end of its trail, the robots go next unless it escaped
+    fn walked(&mut self, ctx: &mut Context<'a>) -> Transition<'a> {
+        if self.state.player.get_position() == self.state.exit {
+            self.phase = Phase::Pose { ticks: POSE_TICKS, result: TurnResult::OK };
+            return Transition::None;
         }
-        
-        if state.policemen[i].get_position() == state.player.pos { return TurnResult::Caught }
-        seen = seen || state.policemen[i].sees(state.player.get_position(), &state.tilemap.tiles);
+        self.robots_turn(ctx)
     }
-    
-    // commandos turn
-    println!("Commandos turn...");
-    for i in 0..state.commandos.len() {
-        let state_copy = state.clone();
-        for tile in state.commandos[i].turn(&state_copy).iter() {
-            state.commandos[i].set_position(*tile);
-            if state.player.sees(*tile, &state.tilemap.tiles) {
-                render(canvas, sprites, state);
+
+    // robots decide their moves one after another, then move all at once;
+    // their sounds are heard once they get there
+    fn robots_turn(&mut self, ctx: &mut Context<'a>) -> Transition<'a> {
+        let (state, sprites, settings, canvas) = (&mut self.state, &self.view.sprites, &ctx.settings, &ctx.canvas);
+        let mut effects: Vec<(&'static str, (f32, f32))> = vec![];
+        let mut visible = false;
+        let mut caught = false;
+        let mut seen = false;
+        // robots see less of the cat on dark tiles
+        let light = state.lights.visibility(state.player.get_position());
+        'robots: {
+            // citizens turn
+            println!("Citizens turn...");
+            for i in 0..state.citizens.len() {
+                let state_copy = state.clone();
+                let calm = state.citizens[i].mode == CitizenState::CALM;
+                let start = state.citizens[i].get_position();
+                let path = state.citizens[i].turn(&state_copy);
+                if let Some(&end) = path.last() { state.citizens[i].set_position(end) }
+                let pose = walking_pose(state.citizens[i].mode == CitizenState::PANIC);
+                state.citizens[i].animation = walk(state, start, &path, sprites.pose("citizen", pose), settings);
+                visible = visible || path.iter().any(|&tile| state.player.sees(tile, &state.tilemap.tiles));
+                if calm && state.citizens[i].mode == CitizenState::PANIC {
+                    effects.push(("panic", sound_position(canvas, state, state.citizens[i].get_position())));
+                }
+                seen = seen || state.citizens[i].sees_in_light(state.player.get_position(), &state.tilemap.tiles, light);
+            }
+
+            // policemen turn
+            println!("Policemen turn...");
+            for i in 0..state.policemen.len() {
+                let state_copy = state.clone();
+                let saw_player = state.policemen[i].sees_in_light(state.player.get_position(), &state.tilemap.tiles, light);
+                let start = state.policemen[i].get_position();
+                let path = state.policemen[i].turn(&state_copy);
+                if let Some(&end) = path.last() { state.policemen[i].set_position(end) }
+                let sees_player = state.policemen[i].sees_in_light(state.player.get_position(), &state.tilemap.tiles, light);
+                state.policemen[i].animation = walk(state, start, &path, sprites.pose("police", walking_pose(sees_player)), settings);
+                visible = visible || path.iter().any(|&tile| state.player.sees(tile, &state.tilemap.tiles));
+
+                if state.policemen[i].get_position() == state.player.pos {
+                    caught = true;
+                    break 'robots;
+                }
+                if sees_player && !saw_player {
+                    effects.push(("police_alert", sound_position(canvas, state, state.policemen[i].get_position())));
+                }
+                seen = seen || sees_player;
+            }
+
+            // commandos turn
+            println!("Commandos turn...");
+            for i in 0..state.commandos.len() {
+                let state_copy = state.clone();
+                let saw_player = state.commandos[i].sees_in_light(state.player.get_position(), &state.tilemap.tiles, light);
+                let start = state.commandos[i].get_position();
+                let path = state.commandos[i].turn(&state_copy);
+                if let Some(&end) = path.last() { state.commandos[i].set_position(end) }
+                let sees_player = state.commandos[i].sees_in_light(state.player.get_position(), &state.tilemap.tiles, light);
+                state.commandos[i].animation = walk(state, start, &path, sprites.pose("commando", walking_pose(sees_player)), settings);
+                visible = visible || path.iter().any(|&tile| state.player.sees(tile, &state.tilemap.tiles));
+
+                if state.commandos[i].get_position() == state.player.pos {
+                    caught = true;
+                    break 'robots;
+                }
+                if sees_player && !saw_player {
+                    effects.push(("commando_alert", sound_position(canvas, state, state.commandos[i].get_position())));
+                }
+                seen = seen || sees_player;
             }
         }
-        
-        if state.commandos[i].get_position() == state.player.pos { return TurnResult::Caught }
-        seen = seen || state.commandos[i].sees(state.player.get_position(), &state.tilemap.tiles);
+
+        // robots the cat can't see just appear where they went
+        if visible {
+            self.phase = Phase::Robots { effects, caught, seen };
+            return Transition::None;
+        }
+        stop_robots(state);
+        self.robots_arrived(ctx, effects, caught, seen)
     }
-    
-    if seen { 
-        return TurnResult::Detected;
+
+    fn robots_arrived(&mut self, ctx: &mut Context<'a>, effects: Vec<(&'static str, (f32, f32))>, caught: bool, seen: bool) -> Transition<'a> {
+        for (name, position) in effects {
+            ctx.music_mixer.play_effect_at(name, position);
+        }
+        if caught {
+            ctx.music_mixer.play_effect("caught");
+            self.phase = Phase::Pose { ticks: POSE_TICKS, result: TurnResult::Caught };
+            return Transition::None;
+        }
+        self.end_turn(ctx, if seen { TurnResult::Detected } else { TurnResult::OK })
     }
-    else { 
-        return TurnResult::OK;
+
+    fn end_turn(&mut self, ctx: &mut Context<'a>, result: TurnResult) -> Transition<'a> {
+        match result {
+            TurnResult::Caught => { 
+                return self.defeat(ctx)
+            },
+            TurnResult::Detected => {
+                // only warn when the chase starts, not every turn it goes on
+                if self.threat.level == 0 { ctx.music_mixer.play_effect("detected") }
+                self.state.times_detected += 1;
+            },
+            TurnResult::OK => {
+                if self.state.player.pos == self.state.exit {
+                    return self.complete(ctx);
+                }
+            },
+        }
+        self.threat.update(threat::score(&self.state));
+        ctx.music_mixer.play_song(self.threat.track(&self.music));
+        // the path has to be found again from the new position
+        self.state.move_to = self.state.player.get_position();
+        Transition::None
     }
 }
 
+// where a sound made at `pos` comes from, heard by the cat: pan from -1 (left) to 1 (right) and distance
+// from 0 to 1, both measured on screen, so robots off screen are panned all the way to their side
+fn sound_position(canvas: &WindowCanvas, state: &State, pos: (usize, usize)) -> (f32, f32) {
+    let (width, height) = canvas.output_size().unwrap();
+    let (x, y) = state.tilemap.get_tile_pos(pos.0, pos.1);
+    let (row, col) = state.player.get_position();
+    let (cat_x, cat_y) = state.tilemap.get_tile_pos(row, col);
+    let scale = state.tilemap.scale;
+    let dx = (x - cat_x) as f32 * scale / (width as f32 / 2.0);
+    let dy = (y - cat_y) as f32 * scale / (height as f32 / 2.0);
+    (dx.clamp(-1.0, 1.0), (dx * dx + dy * dy).sqrt().min(1.0))
+}
+
 struct Drawable {
     texture: String,
     x: i32,
@@ -310,9 +673,106 @@ impl Drawable {
     }
 }
 
-fn render(canvas: &mut WindowCanvas, sprites: &mut HashMap<String, Texture>, state: &mut State) {
+// animation along a path, None if the entity stays put
+fn walk(state: &State, start: (usize, usize), path: &[(usize, usize)], sprites: SpriteAnimation, settings: &Settings) -> Option<Animation> {
+    if path.is_empty() { return None }
+    let points = std::iter::once(&start)
+        .chain(path.iter())
+        .map(|&(row, col)| state.tilemap.get_tile_pos(row, col))
+        .collect();
+    Some(Animation::init(points, sprites, settings.tile_duration()))
+}
+
+fn robots_moving(state: &State) -> bool {
+    state.citizens.iter().any(|citizen| citizen.animation.is_some())
+        || state.policemen.iter().any(|policeman| policeman.animation.is_some())
+        || state.commandos.iter().any(|commando| commando.animation.is_some())
+}
+
+fn stop_robots(state: &mut State) {
+    state.citizens.iter_mut().for_each(|citizen| citizen.animation = None);
+    state.policemen.iter_mut().for_each(|policeman| policeman.animation = None);
+    state.commandos.iter_mut().for_each(|commando| commando.animation = None);
+}
 
+// advance a robot along its path, it keeps facing the way it last moved
+fn step(animation: &mut Option<Animation>, flipped: &mut bool) {
+    let Some(current) = animation.as_mut() else { return };
+    let (_, _, finished, facing) = current.next_frame();
+    *flipped = facing.unwrap_or(*flipped);
+    if finished {
+        *animation = None;
+    }
+}
+
+// advance the animations and the camera by one clock tick
+fn tick(state: &mut State) {
+    state.ticks += 1;
     state.tilemap.calc_view();
+    for citizen in state.citizens.iter_mut() {
+        step(&mut citizen.animation, &mut citizen.flipped);
+    }
+    for policeman in state.policemen.iter_mut() {
+        step(&mut policeman.animation, &mut policeman.flipped);
+    }
+    for commando in state.commandos.iter_mut() {
+        step(&mut commando.animation, &mut commando.flipped);
+    }
+    let Some(animation) = state.player.animation.as_mut() else { return };
+    let ((x, y), _, finished, flipped) = animation.next_frame();
+    state.player.pos = state.tilemap.get_tile_index(x+14, y+9);
+    state.player.flipped = flipped.unwrap_or(state.player.flipped);
+    if finished {
+        println!("Animation finished");
+        state.player.animation = None;
+    }
+}
+
+fn walking_pose(alert: bool) -> Pose {
+    if alert { Pose::Alert } else { Pose::Walk }
+}
+
+// robots are drawn along their path while they move; standing, they cheer on the cat's tile
+// and face the cat while they see it
+#[allow(clippy::too_many_arguments)]
+fn robot_drawable(
+    state: &State,
+    sprites: &Sprites,
+    character: &str,
+    pos: (usize, usize),
+    animation: &Option<Animation>,
+    flipped: bool,
+    alert: bool,
+    alpha: f32
+) -> Drawable {
+    let tilemap = &state.tilemap;
+    if let Some(animation) = animation {
+        let (x, y) = animation.position(alpha);
+        return Drawable::init(animation.sprite().to_string(), x, y, flipped, tilemap.get_tile_index(x+14, y+9));
+    }
+    let (x, y) = tilemap.get_tile_pos(pos.0, pos.1);
+    let (cat_x, _) = tilemap.get_tile_pos(state.player.pos.0, state.player.pos.1);
+    let (pose, flipped) = match (pos == state.player.pos, alert) {
+        (true, _) => (Pose::Victory, flipped),
+        (false, true) => (Pose::Alert, cat_x < x),
+        (false, false) => (Pose::Idle, flipped)
+    };
+    // so that robots don't all blink at once
+    let phase = (pos.0 * 7 + pos.1 * 13) as u64 * 100;
+    let sprite = sprites.pose(character, pose).frame_at(animation_time(state) + phase).to_string();
+    Drawable::init(sprite, x, y, flipped, pos)
+}
+
+// milliseconds of animation so far, for looping poses
+fn animation_time(state: &State) -> u64 {
+    state.ticks as u64 * TICK.as_millis() as u64
+}
+
+// draw the current frame without presenting it, alpha is how far it is between the last two ticks
+fn draw(canvas: &mut WindowCanvas, view: &mut View, state: &mut State, debug: bool, alpha: f32) {
+    let sprites = &view.sprites;
+
+    state.tilemap.interpolate_view(alpha);
 
     let mut drawables: Vec<Drawable> = vec![];
 
@@ -330,7 +790,7 @@ fn render(canvas: &mut WindowCanvas, sprites: &mut HashMap<String, Texture>, sta
                         _ => {
                             if drow == 1 && dcol == 1 {
                                 if state.tilemap.tiles[row-1][col] == TileType::Wall && state.tilemap.tiles[row][col-1] == TileType::Wall {
-                                    drawables.push(Drawable::init("border_corner".to_string(), x+12, y+1, false, (row, col)));
+                                    drawables.push(Drawable::init("border_corner".to_string(), x, y, false, (row, col)));
                                 }
                             }
                         }
@@ -339,7 +799,7 @@ fn render(canvas: &mut WindowCanvas, sprites: &mut HashMap<String, Texture>, sta
                         TileType::Wall | TileType::None => {},
                         _ => {
                             if drow == 1 {
-                                drawables.push(Drawable::init("border_left".to_string(), x+12, y+1, false, (row, col)));
+                                drawables.push(Drawable::init("border_left".to_string(), x, y, false, (row, col)));
                             }
                         }
                     }
@@ -347,7 +807,7 @@ fn render(canvas: &mut WindowCanvas, sprites: &mut HashMap<String, Texture>, sta
                         TileType::Wall | TileType::None => {},
                         _ => {
                             if dcol == 1 {
-                                drawables.push(Drawable::init("border_right".to_string(), x, y+1, false, (row, col)));
+                                drawables.push(Drawable::init("border_right".to_string(), x, y, false, (row, col)));
                             }
                         }
                     }
@@ -357,10 +817,10 @@ fn render(canvas: &mut WindowCanvas, sprites: &mut HashMap<String, Texture>, sta
                             if drow == -1 {
                                 match state.tilemap.tiles[row][col-1] {
                                     TileType::Wall | TileType::None => {
-                                        drawables.push(Drawable::init("wall_right".to_string(), x, y-9, false, (row, col)));
+                                        drawables.push(Drawable::init("wall_right".to_string(), x, y, false, (row, col)));
                                     },
                                     _ => {
-                                        drawables.push(Drawable::init("wall_right_transparent".to_string(), x, y-9, false, (row, col)));
+                                        drawables.push(Drawable::init("wall_right_transparent".to_string(), x, y, false, (row, col)));
                                     }
                                 }
                             }
@@ -372,17 +832,17 @@ fn render(canvas: &mut WindowCanvas, sprites: &mut HashMap<String, Texture>, sta
                             if dcol == -1 {
                                 match state.tilemap.tiles[row-1][col] {
                                     TileType::Wall | TileType::None => {
-                                        drawables.push(Drawable::init("wall_left".to_string(), x+12, y-9, false, (row, col)));
+                                        drawables.push(Drawable::init("wall_left".to_string(), x, y, false, (row, col)));
                                     },
                                     _ => {
-                                        drawables.push(Drawable::init("wall_left_transparent".to_string(), x+12, y-9, false, (row, col)));
+                                        drawables.push(Drawable::init("wall_left_transparent".to_string(), x, y, false, (row, col)));
                                     }
                                 }
                             }
                         }
                     }
                 },
-                TileType::Liquid => { drawables.push(Drawable::init("liquid".to_string(), x, y+3, false, (row, col))); },
+                TileType::Liquid => { drawables.push(Drawable::init("liquid".to_string(), x, y, false, (row, col))); },
                 TileType::None => {}
             }
         }
@@ -393,111 +853,124 @@ fn render(canvas: &mut WindowCanvas, sprites: &mut HashMap<String, Texture>, sta
         let (x, y) = state.tilemap.get_tile_pos(*row, *col);
         drawables.push(Drawable::init("highlight".to_string(), x, y, false, (*row, *col)));
     }
+    if state.keyboard_cursor && !state.trail.contains(&state.cursor) {
+        let (row, col) = state.cursor;
+        let (x, y) = state.tilemap.get_tile_pos(row, col);
+        drawables.push(Drawable::init("highlight".to_string(), x, y, false, (row, col)));
+    }
  
     // add exit
     {
         let (x, y) = state.tilemap.get_tile_pos(state.exit.0, state.exit.1);
-        drawables.push(Drawable::init("exit".to_string(), x, y-16, false, (state.exit.0, state.exit.1)));
+        drawables.push(Drawable::init("exit".to_string(), x, y, false, (state.exit.0, state.exit.1)));
+    }
+
+    // add collectibles
+    for collectible in state.collectibles.iter().filter(|c| !c.collected) {
+        let (row, col) = collectible.pos;
+        let (x, y) = state.tilemap.get_tile_pos(row, col);
+        drawables.push(Drawable::init(collectible.kind.sprite().to_string(), x, y, false, (row, col)));
     }
 
     // add cat
     {
-        let (x, y, sprite, finished, flipped);
-        if state.animation.is_none() {
-            let (row, col) = state.player.get_position();
-            (x, y) = state.tilemap.get_tile_pos(row as usize, col as usize);
-        }
-        else {
-            ((x, y), sprite, finished, flipped) = state.animation.as_mut().unwrap().next_frame();
-            state.player.pos = state.tilemap.get_tile_index(x+14, y+9);
-            state.player.flipped = flipped.unwrap_or(state.player.flipped);
-            state.player.current_sprite = sprite.to_string();
-            if finished {
-                println!("Animation finished");
-                state.player.current_sprite = "cat_idle_1".to_string();
-                state.animation = None;
+        let (x, y) = match &state.player.animation {
+            Some(animation) => animation.position(alpha),
+            None => {
+                let (row, col) = state.player.get_position();
+                state.tilemap.get_tile_pos(row, col)
             }
-        }
-        drawables.push(Drawable::init(state.player.current_sprite.clone(), x+6, y-6, state.player.flipped, state.player.get_position()));
+        };
+        let sprite = match &state.player.animation {
+            Some(animation) => animation.sprite().to_string(),
+            None => {
+                let caught = state.policemen.iter().map(|p| p.get_position())
+                    .chain(state.commandos.iter().map(|c| c.get_position()))
+                    .any(|pos| pos == state.player.pos);
+                let pose = match (caught, state.player.pos == state.exit) {
+                    (true, _) => Pose::Caught,
+                    (false, true) => Pose::Victory,
+                    (false, false) => Pose::Idle
+                };
+                sprites.pose("cat", pose).frame_at(animation_time(state)).to_string()
+            }
+        };
+        drawables.push(Drawable::init(sprite, x, y, state.player.flipped, state.player.get_position()));
     }
 
     // add citizens
+    let light = state.lights.visibility(state.player.get_position());
     for citizen in state.citizens.iter() {
-        let (row, col) = citizen.get_position();
-        let (x, y) = state.tilemap.get_tile_pos(row as usize, col as usize);
-        match citizen.mode {
-            CitizenState::CALM => {
-                drawables.push(Drawable::init("citizen_calm".to_string(), x+6, y-6, false, (row, col)));
-            },
-            CitizenState::PANIC => {
-                drawables.push(Drawable::init("citizen_alert".to_string(), x+6, y-6, false, (row, col)));
-            }
-        }
+        let alert = citizen.mode == CitizenState::PANIC;
+        drawables.push(robot_drawable(state, sprites, "citizen", citizen.get_position(), &citizen.animation, citizen.flipped, alert, alpha));
     }
     
     // add policemen
     for policeman in state.policemen.iter() {
-        let (row, col) = policeman.get_position();
-        let (x, y) = state.tilemap.get_tile_pos(row as usize, col as usize);
-        match policeman.sees(state.player.get_position(), &state.tilemap.tiles) {
-            true => {
-                drawables.push(Drawable::init("police_alert".to_string(), x+6, y-6, false, (row, col)));
-
-            },
-            false => {
-                drawables.push(Drawable::init("police_calm".to_string(), x+6, y-6, false, (row, col)));
-            }
-        }
+        let alert = policeman.sees_in_light(state.player.get_position(), &state.tilemap.tiles, light);
+        drawables.push(robot_drawable(state, sprites, "police", policeman.get_position(), &policeman.animation, policeman.flipped, alert, alpha));
     }
     
     // add commandos
     for commando in state.commandos.iter() {
-        let (row, col) = commando.get_position();
-        let (x, y) = state.tilemap.get_tile_pos(row as usize, col as usize);
-        match commando.sees(state.player.get_position(), &state.tilemap.tiles) {
-            true => {
-                drawables.push(Drawable::init("commando_alert".to_string(), x+6, y-6, false, (row, col)));
-            },
-            false => {
-                drawables.push(Drawable::init("commando_calm".to_string(), x+6, y-6, false, (row, col)));
-            }
-        }
+        let alert = commando.sees_in_light(state.player.get_position(), &state.tilemap.tiles, light);
+        drawables.push(robot_drawable(state, sprites, "commando", commando.get_position(), &commando.animation, commando.flipped, alert, alpha));
     }
 
+    // the world is drawn at one pixel per art pixel into the target, which is then scaled into the viewport
+    let (scale, viewport) = (state.tilemap.scale, state.tilemap.viewport);
+    let (translation_x, translation_y) = (state.tilemap.translation_x, state.tilemap.translation_y);
+    // art-space point at the target's top left corner, and how much of the target is used
+    let origin_x = ((viewport.x() - translation_x) as f32 / scale).floor() as i32;
+    let origin_y = ((viewport.y() - translation_y) as f32 / scale).floor() as i32;
+    let target_width = (viewport.width() as f32 / scale).ceil() as u32 + 1;
+    let target_height = (viewport.height() as f32 / scale).ceil() as u32 + 1;
+    view.fit_target(target_width, target_height);
+    let sprites = &mut view.sprites;
+    let target = view.target.as_mut().unwrap();
+
+    let (rows, cols) = (state.tilemap.tiles.len(), state.tilemap.tiles.iter().map(|row| row.len()).max().unwrap_or(0));
+    let light_map = state.lights.at(animation_time(state), rows, cols);
+
     // sort and draw everything
-    canvas.set_draw_color(Color::BLACK);
-    canvas.clear();
     drawables.sort_by_key(|d| d.key);
-    for drawable in drawables.iter() {
-        let tex = sprites.get_mut(drawable.texture.as_str()).unwrap();
-        let (row, col) = drawable.key;
-        if !state.player.sees((row, col), &state.tilemap.tiles) {
-            if DEBUG { tex.set_color_mod(128, 128, 128); }
-            else { continue; }
-        }
-        else if !DEBUG {
-            let distance = state.player.distance_to((row, col));
-            let color = 256.0 * (1.0 - (distance / Player::VIEW_DISTANCE as f32).powf(2.0)).max(0.0);
-            let color = color as u8;
-            tex.set_color_mod(color, color, color); 
+    canvas.with_texture_canvas(target, |canvas| {
+        canvas.set_draw_color(Color::BLACK);
+        canvas.clear();
+        for drawable in drawables.iter() {
+            let (row, col) = drawable.key;
+            let mut color = Color::WHITE;
+            if !state.player.sees((row, col), &state.tilemap.tiles) {
+                if debug { color = Color::RGB(128, 128, 128); }
+                else { continue; }
+            }
+            else if !debug {
+                // the cat's own light, plus the level's lights
+                let own = light::falloff(state.player.distance_to((row, col)), Player::VIEW_DISTANCE as f32);
+                let (r, g, b) = light_map[row][col];
+                let tint = |light: f32| (255.0 * (own + light).min(1.0)) as u8;
+                color = Color::RGB(tint(r), tint(g), tint(b));
+            }
+            // each sprite knows where it sits on its tile
+            let (offset_x, offset_y) = sprites.offset(&drawable.texture);
+            let (width, height) = sprites.size(&drawable.texture);
+            let rect = Rect::new(drawable.x + offset_x - origin_x, drawable.y + offset_y - origin_y, width, height);
+            sprites.draw(canvas, &drawable.texture, rect, drawable.flipped, color);
         }
-        canvas.copy_ex(
-            tex, 
-            None,
-            Rect::new(
-                drawable.x * state.tilemap.scale as i32 + state.tilemap.translation_x, 
-                drawable.y * state.tilemap.scale as i32 + state.tilemap.translation_y, 
-                tex.query().width * (state.tilemap.scale as u32), 
-                tex.query().height * (state.tilemap.scale as u32)
-            ),
-            0.0, 
-            None, 
-            drawable.flipped, 
-            false
-        ).unwrap();
-        tex.set_color_mod(255, 255, 255);
-    }
-    canvas.present();
-    std::thread::sleep(std::time::Duration::from_millis(FRAME_DURATION));
-    state.seen_timer = (state.seen_timer - FRAME_DURATION as i32).max(0);
+    }).unwrap();
+
+    // nearest neighbour scaling, whole or fractional, with black bars around the viewport when letterboxed
+    canvas.set_draw_color(Color::BLACK);
+    canvas.clear();
+    let dst = Rect::new(
+        (origin_x as f32 * scale).round() as i32 + translation_x,
+        (origin_y as f32 * scale).round() as i32 + translation_y,
+        (target_width as f32 * scale).round() as u32,
+        (target_height as f32 * scale).round() as u32
+    );
+    canvas.set_clip_rect(viewport);
+    canvas.copy(target, Rect::new(0, 0, target_width, target_height), dst).unwrap();
+    canvas.set_clip_rect(None);
+
+    view.hud.draw(canvas, Some(state.lights.visibility(state.player.get_position())));
 } 
@@ -0,0 +1,157 @@
+use serde::{Deserialize, Serialize};
+
+use crate::entity::{Entity, Sight};
+use crate::map::{Map, TileType};
+
+// no light reaches further than this, in tiles
+const MAX_RADIUS: usize = 12;
+
+// where a light is
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LightSource {
+    // a lamp standing on a floor tile
+    Lamp { pos: (usize, usize) },
+    // every liquid tile glows
+    Liquid,
+    Exit
+}
+
+// lights listed under "lights" in the level file, e.g.
+// [{"type": "lamp", "pos": [1, 8], "radius": 3.5, "color": [255, 200, 120], "flicker": 0.1},
+//  {"type": "liquid", "radius": 1.5, "color": [80, 200, 120]}]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Light {
+    #[serde(flatten)]
+    pub source: LightSource,
+    // in tiles
+    pub radius: f32,
+    #[serde(default = "white")]
+    pub color: (u8, u8, u8),
+    // 0 for a steady light, up to 1 for one that almost goes out
+    #[serde(default, skip_serializing_if = "is_steady")]
+    pub flicker: f32
+}
+
+fn white() -> (u8, u8, u8) {
+    (255, 255, 255)
+}
+
+fn is_steady(flicker: &f32) -> bool {
+    *flicker == 0.0
+}
+
+// a light placed on one tile, with the tiles it reaches
+#[derive(Clone)]
+struct Lamp {
+    pos: (usize, usize),
+    color: (f32, f32, f32),
+    flicker: f32,
+    // so lamps don't flicker in step
+    phase: f32,
+    // tiles in its radius it can see, with how much light they get (0 to 1)
+    tiles: Vec<((usize, usize), f32)>
+}
+
+impl Entity for Lamp {
+    const SPEED: usize = 0;
+    fn get_position(&self) -> (usize, usize) { self.pos }
+    fn set_position(&mut self, tile: (usize, usize)) {
+        self.pos = tile;
+    }
+}
+
+// walls cast shadows the same way they block a robot's view
+impl Sight for Lamp {
+    const VIEW_DISTANCE: usize = MAX_RADIUS;
+}
+
+// light reaching every tile from the level's lights, what each lamp reaches is worked out once,
+// only flicker changes over time
+#[derive(Clone)]
+pub struct LightMap {
+    lamps: Vec<Lamp>,
+    // brightness of every tile (0 to 1) as robots see it, flicker averaged out so turns don't depend on timing
+    steady: Vec<Vec<f32>>
+}
+
+impl LightMap {
+    pub fn init(lights: &[Light], tilemap: &Map, exit: (usize, usize)) -> Self {
+        let mut lamps: Vec<Lamp> = vec![];
+        for light in lights.iter() {
+            let positions: Vec<(usize, usize)> = match light.source {
+                LightSource::Lamp { pos } => vec![pos],
+                LightSource::Exit => vec![exit],
+                LightSource::Liquid => (0..tilemap.tiles.len())
+                    .flat_map(|row| (0..tilemap.tiles[row].len()).map(move |col| (row, col)))
+                    .filter(|&(row, col)| tilemap.tiles[row][col] == TileType::Liquid)
+                    .collect()
+            };
+            let (r, g, b) = light.color;
+            for pos in positions {
+                let mut lamp = Lamp {
+                    pos,
+                    color: (r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0),
+                    flicker: light.flicker.clamp(0.0, 1.0),
+                    phase: (pos.0 * 31 + pos.1 * 17) as f32,
+                    tiles: vec![]
+                };
+                lamp.tiles = lit_tiles(&lamp, light.radius.min(MAX_RADIUS as f32), &tilemap.tiles);
+                lamps.push(lamp);
+            }
+        }
+        let mut steady: Vec<Vec<f32>> = tilemap.tiles.iter().map(|row| vec![0.0; row.len()]).collect();
+        for lamp in lamps.iter() {
+            let (r, g, b) = lamp.color;
+            let luminance = 0.299 * r + 0.587 * g + 0.114 * b;
+            for &((row, col), amount) in lamp.tiles.iter() {
+                steady[row][col] += amount * luminance * (1.0 - lamp.flicker / 2.0);
+            }
+        }
+        Self { lamps, steady }
+    }
+
+    // how well robots can see the cat on a tile, from 0 (dark) to 1; levels without lights are fully lit
+    pub fn visibility(&self, tile: (usize, usize)) -> f32 {
+        if self.lamps.is_empty() { return 1.0 }
+        self.steady[tile.0][tile.1].min(1.0)
+    }
+
+    // light on every tile, as red, green and blue from 0 (dark) up, at some point in time (milliseconds)
+    pub fn at(&self, millis: u64, rows: usize, cols: usize) -> Vec<Vec<(f32, f32, f32)>> {
+        let mut light = vec![vec![(0.0, 0.0, 0.0); cols]; rows];
+        let t = millis as f32;
+        for lamp in self.lamps.iter() {
+            // two waves out of step look more like a flame than one
+            let wave = ((t / 90.0 + lamp.phase).sin() + (t / 37.0 + lamp.phase * 2.3).sin()) / 2.0;
+            let brightness = 1.0 - lamp.flicker * (0.5 + 0.5 * wave);
+            for &((row, col), amount) in lamp.tiles.iter() {
+                if row >= rows || col >= cols { continue }
+                let amount = amount * brightness;
+                let (r, g, b) = light[row][col];
+                light[row][col] = (r + lamp.color.0 * amount, g + lamp.color.1 * amount, b + lamp.color.2 * amount);
+            }
+        }
+        light
+    }
+}
+
+// fades out towards the edge of the radius, like the cat's own light
+pub fn falloff(distance: f32, radius: f32) -> f32 {
+    (1.0 - (distance / radius).powf(2.0)).max(0.0)
+}
+
+fn lit_tiles(lamp: &Lamp, radius: f32, tiles: &Vec<Vec<TileType>>) -> Vec<((usize, usize), f32)> {
+    let (row, col) = lamp.pos;
+    let reach = radius.ceil() as usize;
+    let mut lit = vec![];
+    for r in row.saturating_sub(reach)..(row + reach + 1).min(tiles.len()) {
+        for c in col.saturating_sub(reach)..(col + reach + 1).min(tiles[r].len()) {
+            let amount = falloff(lamp.distance_to((r, c)), radius);
+            if amount > 0.0 && lamp.sees((r, c), tiles) {
+                lit.push(((r, c), amount));
+            }
+        }
+    }
+    lit
+}
@@ -1,19 +1,193 @@
 use serde::{Deserialize, Serialize};
 
+use crate::light::{Light, LightSource};
+use crate::map::TileType;
+use crate::objective::Objective;
+
+// entity letters used in ascii maps, on top of the tile legend from `TileType::symbol`
+// (every entity stands on a floor tile)
+const PLAYER: char = 'P';
+const EXIT: char = 'E';
+const CITIZEN: char = 'c';
+const POLICEMAN: char = 'p';
+const COMMANDO: char = 'k';
+const FISH: char = 'f';
+const BELL: char = 'b';
+
 #[derive(Serialize, Deserialize)]
 pub struct LevelFile {
     pub name: String,
+    // ascii map, one string per row (replaces `tilemap` and entity coordinates)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub map: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub tilemap: Vec<Vec<u32>>,
+    #[serde(default, skip_serializing_if = "is_unset")]
     pub player: (usize, usize),
+    #[serde(default, skip_serializing_if = "is_unset")]
     pub exit: (usize, usize),
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub citizens: Vec<(usize, usize)>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub policemen: Vec<(usize, usize)>,
-    pub commandos: Vec<(usize, usize)>
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub commandos: Vec<(usize, usize)>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fish: Vec<(usize, usize)>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bells: Vec<(usize, usize)>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub objectives: Vec<Objective>,
+    // music tracks from the audio manifest, from calm to most intense
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub music: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub lights: Vec<Light>
+}
+
+// (0, 0) is always part of the padding added around the tilemap, so nothing can stand there
+fn is_unset(pos: &(usize, usize)) -> bool {
+    *pos == (0, 0)
+}
+
+impl LevelFile {
+    // fill in tilemap and entity positions from the ascii map
+    fn parse_map(&mut self) -> Result<(), String> {
+        self.tilemap = vec![];
+        for (row, line) in self.map.iter().enumerate() {
+            let mut tiles: Vec<u32> = vec![];
+            for (col, symbol) in line.chars().enumerate() {
+                // entity coordinates include the padding added by `Map::load`
+                let pos = (row+1, col+1);
+                let tile = match symbol {
+                    PLAYER if !is_unset(&self.player) => { return Err(format!("Second player at row {row}, column {col}")) },
+                    EXIT if !is_unset(&self.exit) => { return Err(format!("Second exit at row {row}, column {col}")) },
+                    PLAYER => { self.player = pos; TileType::Floor },
+                    EXIT => { self.exit = pos; TileType::Floor },
+                    CITIZEN => { self.citizens.push(pos); TileType::Floor },
+                    POLICEMAN => { self.policemen.push(pos); TileType::Floor },
+                    COMMANDO => { self.commandos.push(pos); TileType::Floor },
+                    FISH => { self.fish.push(pos); TileType::Floor },
+                    BELL => { self.bells.push(pos); TileType::Floor },
+                    _ => TileType::from_symbol(symbol)
+                        .ok_or(format!("Unknown symbol '{symbol}' at row {row}, column {col}"))?
+                };
+                tiles.push(tile.id());
+            }
+            self.tilemap.push(tiles);
+        }
+        // lamps are placed by row and column in the ascii map
+        for light in self.lights.iter_mut() {
+            if let LightSource::Lamp { pos } = &mut light.source {
+                if self.tilemap.get(pos.0).is_none_or(|tiles| pos.1 >= tiles.len()) {
+                    return Err(format!("Lamp at row {}, column {} is outside the map", pos.0, pos.1));
+                }
+                *pos = (pos.0+1, pos.1+1);
+            }
+        }
+        if is_unset(&self.player) { return Err("Level has no player".to_string()); }
+        if is_unset(&self.exit) { return Err("Level has no exit".to_string()); }
+        Ok(())
+    }
+
+    // convert a level into its ascii form, dropping the tilemap and coordinate lists
+    pub fn to_ascii(&self) -> Result<LevelFile, String> {
+        let mut rows: Vec<Vec<char>> = self.tilemap
+            .iter()
+            .map(|row| row.iter().map(|&id| TileType::from_id(id).symbol()).collect())
+            .collect();
+        // coordinates include the padding around the tilemap, so row and column 0 are outside of it
+        let mut place = |(row, col): (usize, usize), symbol: char| -> Result<(), String> {
+            let tile = row.checked_sub(1)
+                .zip(col.checked_sub(1))
+                .and_then(|(row, col)| rows.get_mut(row).and_then(|tiles| tiles.get_mut(col)))
+                .ok_or(format!("'{symbol}' at ({row}, {col}) is outside the map"))?;
+            *tile = symbol;
+            Ok(())
+        };
+        self.citizens.iter().try_for_each(|&pos| place(pos, CITIZEN))?;
+        self.policemen.iter().try_for_each(|&pos| place(pos, POLICEMAN))?;
+        self.commandos.iter().try_for_each(|&pos| place(pos, COMMANDO))?;
+        self.fish.iter().try_for_each(|&pos| place(pos, FISH))?;
+        self.bells.iter().try_for_each(|&pos| place(pos, BELL))?;
+        place(self.exit, EXIT)?;
+        place(self.player, PLAYER)?;
+        let mut lights = self.lights.clone();
+        for light in lights.iter_mut() {
+            if let LightSource::Lamp { pos } = &mut light.source {
+                let inside = pos.0 >= 1 && pos.1 >= 1
+                    && rows.get(pos.0-1).is_some_and(|tiles| pos.1-1 < tiles.len());
+                if !inside { return Err(format!("Lamp at {pos:?} is outside the map")) }
+                *pos = (pos.0-1, pos.1-1);
+            }
+        }
+        Ok(LevelFile {
+            name: self.name.clone(),
+            map: rows.iter().map(|row| row.iter().collect()).collect(),
+            tilemap: vec![],
+            player: (0, 0),
+            exit: (0, 0),
+            citizens: vec![],
+            policemen: vec![],
+            commandos: vec![],
+            fish: vec![],
+            bells: vec![],
+            objectives: self.objectives.clone(),
+            music: self.music.clone(),
+            lights
+        })
+    }
 }
 
 pub fn load_level(path: String) -> LevelFile {
     println!("Loading '{path}'");
     let source = std::fs::read_to_string(path).expect("Failed to read level file");
-    let parsed_level: LevelFile = serde_json::from_str(&source).expect("Failed to parse level file");
-    return parsed_level;
+    let mut parsed_level: LevelFile = serde_json::from_str(&source).expect("Failed to parse level file");
+    if !parsed_level.map.is_empty() {
+        parsed_level.parse_map().expect("Failed to parse level map");
+    }
+    parsed_level
+}
+
+pub fn write_level(level: &LevelFile, path: &str) -> Result<(), String> {
+    let json = compact_numbers(&serde_json::to_string_pretty(level).map_err(|e| e.to_string())?);
+    std::fs::write(path, json + "\n").map_err(|e| e.to_string())?;
+    println!("Saved '{path}'");
+    Ok(())
+}
+
+// `to_string_pretty` puts every number on a line of its own, arrays of numbers (positions, colors)
+// are kept on one line instead, like in the hand-written level files
+fn compact_numbers(json: &str) -> String {
+    let mut compact = String::with_capacity(json.len());
+    // end of the part already copied
+    let mut copied = 0;
+    let (mut in_string, mut escaped) = (false, false);
+    for (i, c) in json.char_indices() {
+        if i < copied { continue }
+        match c {
+            _ if escaped => { escaped = false },
+            '\\' if in_string => { escaped = true },
+            '"' => { in_string = !in_string },
+            '[' if !in_string => {
+                let Some(end) = json[i..].find(']').map(|end| i + end) else { continue };
+                let inner = &json[i+1..end];
+                let numbers = inner.chars().all(|c| c.is_ascii_digit() || c.is_whitespace() || ".,-+eE".contains(c));
+                if inner.trim().is_empty() || !numbers { continue }
+                compact.push_str(&json[copied..i]);
+                compact.push('[');
+                compact.push_str(&inner.split(',').map(str::trim).collect::<Vec<&str>>().join(", "));
+                compact.push(']');
+                copied = end + 1;
+            },
+            _ => {}
+        }
+    }
+    compact.push_str(&json[copied..]);
+    compact
+}
+
+// write the ascii version of a level file, e.g. `cargo run -- --export-ascii old.json new.json`
+pub fn export_ascii(path: String, output: String) -> Result<(), String> {
+    write_level(&load_level(path).to_ascii()?, &output)
 }
@@ -9,94 +9,103 @@ mod player;
 mod robots;
 mod loader;
 mod animation;
+mod sprites;
+mod clock;
 mod splash;
+mod collectible;
+mod objective;
+mod summary;
+mod scores;
+mod settings;
+mod controller;
+mod input;
+mod threat;
+mod volume;
+mod scene;
+mod view;
+mod light;
+mod hud;
+mod editor;
 
-use level::GameResult;
-use menu::MenuAction;
+use settings::Settings;
+use sdl2::video::FullscreenType;
 
-const DEBUG: bool = false;
+// campaign order, names of files in resources/levels
+const LEVELS: [&str; 3] = ["streets", "sewers", "labyrinth"];
 
 fn main() -> Result<(), String> {
+    // level conversion tool, doesn't need a window
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() == 4 && args[1] == "--export-ascii" {
+        return loader::export_ascii(args[2].clone(), args[3].clone());
+    }
+
+    let settings = Settings::load();
+
     let sdl_context = sdl2::init()?;
     let video_subsystem = sdl_context.video()?;
-    let _audio = sdl_context.audio()?;
+    // the game also runs without sound, the mixer falls back to silence
+    let _audio = sdl_context.audio().map_err(|e| println!("Failed to initialize audio: {e}")).ok();
+    let input = input::Input::init(
+        controller::Controllers::init(sdl_context.game_controller()?),
+        settings.bindings.clone()
+    );
 
     let window = video_subsystem.window(
         "Pipaluk",
-        800,
-        600
+        settings.resolution.0,
+        settings.resolution.1
     )
     .position_centered()
     .resizable()
+    .allow_highdpi()
     .build()
     .unwrap();
 
-    let mut canvas = window.into_canvas()
+    // pixel art stays sharp when zoomed to fractional scales
+    sdl2::hint::set("SDL_RENDER_SCALE_QUALITY", "nearest");
+
+    let mut canvas_builder = window.into_canvas();
+    if settings.vsync {
+        canvas_builder = canvas_builder.present_vsync();
+    }
+    let mut canvas = canvas_builder
     .build()
     .unwrap();
+    if settings.fullscreen {
+        canvas.window_mut().set_fullscreen(FullscreenType::True)?;
+    }
 
-    let mut texture_creator = canvas.texture_creator(); 
+    let texture_creator = canvas.texture_creator(); 
 
-    let mut event_pump = sdl_context.event_pump()?;
+    let event_pump = sdl_context.event_pump()?;
 
     let ttf_context = sdl2::ttf::init()
         .map_err(|e| e.to_string())?;
-    let mut font = ttf_context
+    let font = ttf_context
         .load_font("resources/fonts/Minecraft.ttf", 64)?;
 
     let mut music_mixer = mixer::Mixer::init();
+    volume::apply(&settings, &mut music_mixer);
 
-    let levels = ["streets", "sewers", "labyrinth"];
-
-    splash::show_splash(&mut canvas, &texture_creator, &mut event_pump, &mut font, "PIPALUK".to_string(), 0.75, 1500);
-
-    canvas.present();
-    let mut menu_result = menu::show_menu(&mut canvas, &mut texture_creator, &mut event_pump, &mut font, &mut music_mixer);
-        if menu_result == MenuAction::Quit {
-            return Ok(());
-        }
-    'running: loop {
-        menu_result = MenuAction::NewGame;
-        if menu_result == MenuAction::NewGame {
-            let mut i: usize = 0;
-            let n: usize = levels.len();
-            while i < n{
-                let game_result = level::play_level(&mut canvas, &mut texture_creator, &mut event_pump, &mut font, &mut music_mixer, levels[i]);
-                match game_result {
-                    GameResult::Quit => {
-                        break 'running;
-                    },
-                    GameResult::Menu => {
-                        menu_result = menu::show_menu(&mut canvas, &mut texture_creator, &mut event_pump, &mut font, &mut music_mixer);
-                        match menu_result{
-                            MenuAction::Quit => { break 'running },
-                            MenuAction::NewGame => {continue 'running;}
-                        }     
-                    },
-                    GameResult::Defeat => {
-                        splash::show_splash(&mut canvas, &texture_creator, &mut event_pump, &mut font, "You Died".to_string(), 0.75, 4500);
-                    },
-                    GameResult::Victory => {
-                        music_mixer.stop_music();
-                        music_mixer.play_effect("win");
-                        i += 1;
-                        if i == n{
-                            splash::show_splash(&mut canvas, &texture_creator, &mut event_pump, &mut font, "Congratulations you won!".to_string(), 0.75, 4500);
-                            menu_result = menu::show_menu(&mut canvas, &mut texture_creator, &mut event_pump, &mut font, &mut music_mixer);
-                            match menu_result{
-                                MenuAction::Quit => { break 'running },
-                                MenuAction::NewGame => {continue 'running;}
-                            }
-                        }
-                        else {
-                            splash::show_splash(&mut canvas, &texture_creator, &mut event_pump, &mut font, "You escaped!".to_string(), 0.75, 2500);
-                        }
-                        music_mixer.play_song("slow");
-                    }
-                }
-            }
-            break 'running; 
-        }
-    }
+    let mut ctx = scene::Context {
+        canvas,
+        texture_creator: &texture_creator,
+        event_pump,
+        input,
+        font,
+        music_mixer,
+        settings,
+        clock: clock::Clock::init(),
+        indicator: volume::Indicator::init()
+    };
+
+    let menu = Box::new(menu::Menu::main(&mut ctx));
+    let splash = splash::Splash::init(&mut ctx, "PIPALUK".to_string(), 0.75, 1500, Some(menu));
+    scene::SceneStack::init(Box::new(splash)).run(&mut ctx);
+
+    // F11 can toggle fullscreen anywhere, remember how the game was left
+    ctx.settings.fullscreen = ctx.canvas.window().fullscreen_state() != FullscreenType::Off;
+    ctx.settings.save();
     Ok(())
 }
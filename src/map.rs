@@ -1,6 +1,7 @@
+use sdl2::rect::Rect;
 use sdl2::render::{ WindowCanvas };
 
-use crate::{player::Player, entity::Sight};
+use crate::{player::Player, entity::Sight, clock::TICK, settings::Settings};
 
 #[derive(Clone, Copy, PartialEq)]
 pub enum TileType {
@@ -10,6 +11,47 @@ pub enum TileType {
     Liquid
 }
 
+impl TileType {
+    // numeric id used in json tilemaps
+    pub fn id(&self) -> u32 {
+        match self {
+            TileType::None   => 0,
+            TileType::Floor  => 1,
+            TileType::Wall   => 2,
+            TileType::Liquid => 3,
+        }
+    }
+
+    pub fn from_id(id: u32) -> Self {
+        match id {
+            1 => TileType::Floor,
+            2 => TileType::Wall,
+            3 => TileType::Liquid,
+            _ => TileType::None,
+        }
+    }
+
+    // character used in ascii maps and debug prints
+    pub fn symbol(&self) -> char {
+        match self {
+            TileType::Floor  => '.',
+            TileType::Wall   => '#',
+            TileType::None   => ' ',
+            TileType::Liquid => '~',
+        }
+    }
+
+    pub fn from_symbol(symbol: char) -> Option<Self> {
+        match symbol {
+            '.' => Some(TileType::Floor),
+            '#' => Some(TileType::Wall),
+            ' ' => Some(TileType::None),
+            '~' => Some(TileType::Liquid),
+            _ => None
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Map {
     pub tiles: Vec<Vec<TileType>>,
@@ -17,13 +59,27 @@ pub struct Map {
     bottommost: i32,
     leftmost: i32,
     rightmost: i32,
-    desired_translation_x: i32,
-    desired_translation_y: i32,
-    pub scale: u32,
+    // art-space point in the middle of the screen, and the scale, that the camera moves towards
+    desired_camera: (f32, f32, f32),
+    // camera after the last two ticks, rendering happens somewhere in between
+    camera: (f32, f32, f32),
+    previous_camera: (f32, f32, f32),
+    // part of the window the world is drawn in, in pixels
+    pub viewport: Rect,
+    // mouse wheel zoom, relative to the scale that fits the view
+    zoom: f32,
+    // where the camera was panned to in art space, None while it follows the cat
+    pub focus: Option<(f32, f32)>,
+    pub scale: f32,
     pub translation_x: i32,
     pub translation_y: i32
 }
 
+const ZOOM_MIN: f32 = 0.25;
+const ZOOM_MAX: f32 = 4.0;
+// zoom factor of one mouse wheel notch
+const ZOOM_STEP: f32 = 1.25;
+
 impl Map {
     pub fn new() -> Self {
         Self {
@@ -32,9 +88,13 @@ impl Map {
             bottommost: std::i32::MIN,
             leftmost: std::i32::MAX,
             rightmost: std::i32::MIN,
-            desired_translation_x: 0,
-            desired_translation_y: 0,
-            scale: 1,
+            desired_camera: (0.0, 0.0, 1.0),
+            camera: (0.0, 0.0, 0.0),
+            previous_camera: (0.0, 0.0, 0.0),
+            viewport: Rect::new(0, 0, 1, 1),
+            zoom: 1.0,
+            focus: None,
+            scale: 1.0,
             translation_x: 0,
             translation_y: 0
         }
@@ -46,14 +106,14 @@ impl Map {
     }
     
     // get tile pos relative to the top corner of 0,0 in art space
-    pub fn get_tile_pos(&mut self, row: usize, col: usize) -> (i32, i32) {
+    pub fn get_tile_pos(&self, row: usize, col: usize) -> (i32, i32) {
         let x = - 14 - (row as i32)*14 + (col as i32)*14;
         let y = (row as i32)*7 + (col as i32)*7;
         (x, y)
     }
 
     // get tile row and column based on coordinates in art space
-    pub fn get_tile_index(&mut self, x: i32, y: i32) -> (usize, usize) {
+    pub fn get_tile_index(&self, x: i32, y: i32) -> (usize, usize) {
         let row = (2*y-x) / 28;
         let col = (2*y+x) / 28;
         (row as usize, col as usize)
@@ -66,70 +126,120 @@ impl Map {
         (x as u32, y as u32)
     }
 
+    // move the camera towards where it should be, one tick at a time,
+    // closing 10% of the distance every 50ms
     pub fn calc_view(&mut self) {
-        let x = self.desired_translation_x - self.translation_x;
-        let y = self.desired_translation_y - self.translation_y;
-
-        self.translation_x += (x as f32 * 0.1) as i32;
-        self.translation_y += (y as f32 * 0.1) as i32;
+        let smoothing = 1.0 - 0.9_f32.powf(TICK.as_secs_f32() / 0.05);
+        self.previous_camera = self.camera;
+        self.camera.0 += (self.desired_camera.0 - self.camera.0) * smoothing;
+        self.camera.1 += (self.desired_camera.1 - self.camera.1) * smoothing;
+        self.camera.2 += (self.desired_camera.2 - self.camera.2) * smoothing;
     }
 
-    pub fn calc_scale_translation_debug(&mut self, canvas: &mut WindowCanvas) -> u32 {
-        let (canvas_x, canvas_y) = canvas.output_size().unwrap();
-        let (map_x, map_y) = self.get_dimensions();
+    // set the scale and translation used for drawing, alpha is how far the frame is between the last two ticks
+    pub fn interpolate_view(&mut self, alpha: f32) {
+        let lerp = |previous: f32, current: f32| previous + (current - previous) * alpha;
+        let center_x = lerp(self.previous_camera.0, self.camera.0);
+        let center_y = lerp(self.previous_camera.1, self.camera.1);
+        self.scale = lerp(self.previous_camera.2, self.camera.2);
+        self.translation_x = (self.viewport.center().x() as f32 - center_x * self.scale).round() as i32;
+        self.translation_y = (self.viewport.center().y() as f32 - center_y * self.scale).round() as i32;
+    }
 
-        self.scale = (canvas_x as f32 / map_x as f32).min(canvas_y as f32 / map_y as f32) as u32;
-        self.scale = self.scale.max(1);
+    // the first view of a level doesn't glide in
+    fn start_view(&mut self) {
+        if self.camera.2 == 0.0 {
+            self.camera = self.desired_camera;
+            self.previous_camera = self.desired_camera;
+        }
+    }
 
-        self.calc_translation_debug(canvas);
+    // zoom in (positive) or out (negative) by mouse wheel notches, the scale has to be calculated again afterwards
+    pub fn zoom_by(&mut self, notches: i32) {
+        self.zoom = (self.zoom * ZOOM_STEP.powi(notches)).clamp(ZOOM_MIN, ZOOM_MAX);
+    }
 
-        self.scale
+    // move the camera by some screen pixels, it stops following the cat and stays over the map
+    pub fn pan(&mut self, dx: i32, dy: i32) {
+        let (x, y) = self.focus.unwrap_or((self.camera.0, self.camera.1));
+        let x = (x - dx as f32 / self.scale).clamp(self.leftmost as f32, self.rightmost as f32);
+        let y = (y - dy as f32 / self.scale).clamp(self.topmost as f32, self.bottommost as f32);
+        self.focus = Some((x, y));
+        // dragging moves the map right away, without smoothing
+        self.desired_camera = (x, y, self.desired_camera.2);
+        self.camera = (x, y, self.camera.2);
+        self.previous_camera = (x, y, self.previous_camera.2);
     }
 
-    pub fn calc_translation_debug(&mut self, canvas: &mut WindowCanvas) -> (i32, i32) {
+    // scale that shows an area of the map (in art space) in the window, and the viewport for it;
+    // pixel perfect scales are whole numbers, letterboxing keeps the area's shape with black bars around it
+    fn fit_view(&mut self, canvas: &WindowCanvas, area: (u32, u32), settings: &Settings) {
+        // in pixels, which on HiDPI displays is more than the window size
         let (canvas_x, canvas_y) = canvas.output_size().unwrap();
-        let (map_x, map_y) = self.get_dimensions();
-        
-        self.desired_translation_x = - self.leftmost * self.scale as i32 + (canvas_x as i32 - map_x as i32 * self.scale as i32) / 2;
-        self.desired_translation_y = - self.topmost * self.scale as i32 + (canvas_y as i32 - map_y as i32 * self.scale as i32) / 2;
-        
-        (self.desired_translation_x, self.desired_translation_y)
+
+        let fit = (canvas_x as f32 / area.0 as f32).min(canvas_y as f32 / area.1 as f32);
+        let mut scale = fit * self.zoom;
+        if settings.pixel_perfect && scale >= 1.0 {
+            scale = scale.floor();
+        }
+        self.desired_camera.2 = scale;
+
+        self.viewport = match settings.letterbox {
+            true => {
+                let fit = if settings.pixel_perfect { fit.floor().max(1.0) } else { fit };
+                let (width, height) = (((area.0 as f32 * fit) as u32).min(canvas_x), ((area.1 as f32 * fit) as u32).min(canvas_y));
+                Rect::new((canvas_x - width) as i32 / 2, (canvas_y - height) as i32 / 2, width, height)
+            },
+            false => Rect::new(0, 0, canvas_x, canvas_y)
+        };
+    }
+
+    pub fn calc_scale_translation_debug(&mut self, canvas: &mut WindowCanvas, settings: &Settings) -> f32 {
+        // the whole map
+        let area = self.get_dimensions();
+        self.fit_view(canvas, area, settings);
+
+        self.calc_translation_debug();
+
+        self.desired_camera.2
     }
 
-    pub fn calc_scale_translation(&mut self, canvas: &mut WindowCanvas, player_pos: (usize, usize)) -> u32 {
-        let canvas_size = canvas.output_size().unwrap();
-        let canvas_size = (canvas_size.0 as i32, canvas_size.1 as i32);
-        let visible_size = (Player::VIEW_DISTANCE as i32 * 28 + 50, Player::VIEW_DISTANCE as i32 * 19 + 50);
+    pub fn calc_translation_debug(&mut self) -> (f32, f32) {
+        let center = ((self.leftmost + self.rightmost) as f32 / 2.0, (self.topmost + self.bottommost) as f32 / 2.0);
+        let (x, y) = self.focus.unwrap_or(center);
+        self.desired_camera.0 = x;
+        self.desired_camera.1 = y;
+        self.start_view();
+
+        (x, y)
+    }
 
-        self.scale = (canvas_size.0 as f32 / visible_size.0 as f32).min(canvas_size.1 as f32 / visible_size.1 as f32) as u32;
-        self.scale = self.scale.max(1);
+    pub fn calc_scale_translation(&mut self, canvas: &mut WindowCanvas, player_pos: (usize, usize), settings: &Settings) -> f32 {
+        let visible_size = (Player::VIEW_DISTANCE as u32 * 28 + 50, Player::VIEW_DISTANCE as u32 * 19 + 50);
+        self.fit_view(canvas, visible_size, settings);
 
-        self.calc_translation(canvas, player_pos);
+        self.calc_translation(player_pos);
 
-        self.scale
+        self.desired_camera.2
     }
 
-    pub fn calc_translation(&mut self, canvas: &mut WindowCanvas, player_pos: (usize, usize)) -> (i32, i32) {
+    pub fn calc_translation(&mut self, player_pos: (usize, usize)) -> (f32, f32) {
         let player_pos = self.get_tile_pos(player_pos.0, player_pos.1);
-        let canvas_size = canvas.output_size().unwrap();
-        let canvas_size = (canvas_size.0 as i32, canvas_size.1 as i32);
 
-        self.desired_translation_x = (- player_pos.0) * self.scale as i32 + canvas_size.0 / 2 - 8 * self.scale as i32;
-        self.desired_translation_y = (- player_pos.1) * self.scale as i32 + canvas_size.1 / 2 - 8 * self.scale as i32;
-        
-        (self.desired_translation_x, self.desired_translation_y)
+        // the middle of the cat's sprite
+        let (x, y) = self.focus.unwrap_or((player_pos.0 as f32 + 8.0, player_pos.1 as f32 + 8.0));
+        self.desired_camera.0 = x;
+        self.desired_camera.1 = y;
+        self.start_view();
+
+        (x, y)
     }
 
     // debug print tilemap to console
     pub fn print(&self) {
         for row in self.tiles.iter() {
             for cell in row.iter() {
-                match *cell {
-                    TileType::Floor  => { print!(".") }, 
-                    TileType::Wall   => { print!("#") }, 
-                    TileType::None   => { print!(" ") }, 
-                    TileType::Liquid => { print!("~") }, 
-                }
+                print!("{}", cell.symbol());
             }
             println!();
         }
@@ -3,20 +3,83 @@ extern crate sdl2;
 use sdl2::keyboard::Keycode;
 use sdl2::surface::Surface;
 use sdl2::ttf::Font;
-use sdl2::EventPump;
-use sdl2::pixels::Color;
-use sdl2::render::{ WindowCanvas, TextureCreator};
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::render::{ WindowCanvas, TextureCreator, Texture, BlendMode};
 use sdl2::image::LoadTexture;
-use sdl2::video::{WindowContext, FullscreenType};
+use sdl2::video::{WindowContext, FullscreenType, WindowPos};
 use sdl2::rect::{Rect, Point};
 use sdl2::event::Event;
+use sdl2::mouse::MouseButton;
+
+use std::time::Instant;
 
 use crate::mixer::Mixer;
+use crate::input::{self, Action, Binding, ACTIONS};
+use crate::scene::{Context, Scene, Transition};
+use crate::scores::Scores;
+use crate::settings::Settings;
+use crate::volume;
+use crate::level;
+use crate::editor::Editor;
+use crate::sprites::{Pose, Sprites, SpriteAnimation};
+use crate::loader;
+use crate::map::{Map, TileType};
+use crate::LEVELS;
+
+// largest size of the level select previews, in menu coordinates
+const THUMBNAIL_WIDTH: u32 = 240;
+const THUMBNAIL_HEIGHT: u32 = 100;
 
 #[derive(PartialEq, Clone, Copy)]
 pub enum MenuAction {
     Quit,
-    NewGame
+    NewGame,
+    Play(usize),
+    Levels,
+    Options,
+    // level editor, on a level
+    Edit(usize),
+    Back,
+    // options page
+    Resolution,
+    Fullscreen,
+    MasterVolume,
+    Mute,
+    MusicVolume,
+    EffectsVolume,
+    AnimationSpeed,
+    PixelPerfect,
+    Letterbox,
+    Controls,
+    // controls page
+    Rebind(Action),
+    ResetBindings,
+    // pause menu
+    Resume,
+    Restart,
+    QuitToMenu
+}
+
+#[derive(PartialEq, Clone, Copy)]
+enum MenuPage {
+    Main,
+    Levels,
+    Options,
+    Controls,
+    Pause,
+    // asks before an action that loses level progress
+    Confirm(MenuAction)
+}
+
+impl MenuPage {
+    // page that "Back" leads to, root is the page the menu was opened on
+    fn parent(&self, root: MenuPage) -> MenuPage {
+        match self {
+            MenuPage::Controls => MenuPage::Options,
+            MenuPage::Confirm(_) => MenuPage::Pause,
+            _ => root
+        }
+    }
 }
 
 struct MenuButton {
@@ -45,6 +108,12 @@ impl MenuButton {
         }
     }
 
+    // button wide enough to fit its text
+    pub fn fitted(text: String, action: MenuAction, enabled: bool, x: i32, y: i32, height: u32) -> Self {
+        let width = 40 + text.chars().count() as u32 * (height - 10) * 4 / 5;
+        Self::new(text, action, enabled, Rect::new(x, y, width, height))
+    }
+
     pub fn rect(&self, scale: f32) -> Rect {
         Self::scale_rect(self.rect, scale)
     }
@@ -117,129 +186,503 @@ impl MenuButton {
     }
 }
 
-pub fn show_menu(
-    canvas: &mut WindowCanvas, 
-    texture_creator: &TextureCreator<WindowContext>,
-    event_pump: &mut EventPump,
-    font: &mut Font,
-    music_mixer: &mut Mixer
-) -> MenuAction {
-    println!("SHOW MENU");
-    music_mixer.play_song("slow");
-
-    let background = texture_creator.load_texture("resources/images/menu_background.png").unwrap();
-    let foreground = texture_creator.load_texture("resources/images/menu_foreground.png").unwrap();
-
-    let cat = vec![
-        texture_creator.load_texture("resources/images/cat_idle_1.png").unwrap(),
-        texture_creator.load_texture("resources/images/cat_idle_2.png").unwrap()
-    ];
-
-    let mut buttons = vec![
-        MenuButton::new(
-            "New Game".to_string(), 
-            MenuAction::NewGame, 
-            true,
-            Rect::new(0, 20, 340, 60)
-        ),
-        MenuButton::new(
-            "Quit".to_string(), 
-            MenuAction::Quit, 
-            true,
-            Rect::new(0, 100, 260, 60)
-        )
-    ];
+// main menu, or the pause menu on top of a level
+pub struct Menu<'a> {
+    background: Texture<'a>,
+    foreground: Texture<'a>,
+    sprites: Sprites<'a>,
+    cat: SpriteAnimation,
+    // level select previews, only reachable from the main menu
+    thumbnails: Vec<Texture<'a>>,
+    // frozen level frame shown behind the pause menu
+    snapshot: Option<Texture<'a>>,
+    // level the pause menu belongs to, with the deaths on it so far
+    level: (usize, u32),
+    root: MenuPage,
+    page: MenuPage,
+    // action waiting for a new key or button on the controls page
+    rebinding: Option<Action>,
+    buttons: Vec<MenuButton>,
+    // button picked with the keyboard or a controller, None while using the mouse
+    selected: Option<usize>,
+    started: Instant
+}
+
+impl<'a> Menu<'a> {
+    pub fn main(ctx: &mut Context<'a>) -> Self {
+        println!("SHOW MENU");
+        let thumbnails = LEVELS.iter().map(|name| level_thumbnail(ctx.texture_creator, name)).collect();
+        Self::init(ctx, MenuPage::Main, thumbnails, None, (0, 0))
+    }
 
-    let mut counter = 0;
+    // pause menu on top of the current level frame, which has to be drawn but not yet presented
+    pub fn pause(ctx: &mut Context<'a>, level: usize, deaths: u32) -> Self {
+        println!("SHOW PAUSE");
+        let snapshot = Self::snapshot(ctx);
+        Self::init(ctx, MenuPage::Pause, vec![], Some(snapshot), (level, deaths))
+    }
+
+    // asks before an action that loses unsaved work, on top of the current frame like the pause menu
+    pub fn confirm(ctx: &mut Context<'a>, action: MenuAction) -> Self {
+        println!("SHOW CONFIRM");
+        let snapshot = Self::snapshot(ctx);
+        Self::init(ctx, MenuPage::Confirm(action), vec![], Some(snapshot), (0, 0))
+    }
 
-    loop {
+    fn snapshot(ctx: &mut Context<'a>) -> Texture<'a> {
+        let (width, height) = ctx.canvas.output_size().unwrap();
+        let mut pixels = ctx.canvas.read_pixels(None, PixelFormatEnum::ARGB8888).unwrap();
+        let surface = Surface::from_data(&mut pixels, width, height, width * 4, PixelFormatEnum::ARGB8888).unwrap();
+        ctx.texture_creator.create_texture_from_surface(&surface).map_err(|e| e.to_string()).unwrap()
+    }
+
+    fn init(ctx: &mut Context<'a>, root: MenuPage, thumbnails: Vec<Texture<'a>>, snapshot: Option<Texture<'a>>, level: (usize, u32)) -> Self {
+        let texture_creator = ctx.texture_creator;
+        let sprites = Sprites::load(texture_creator, None);
+        let cat = sprites.pose("cat", Pose::Idle);
+        Self {
+            background: texture_creator.load_texture("resources/images/menu_background.png").unwrap(),
+            foreground: texture_creator.load_texture("resources/images/menu_foreground.png").unwrap(),
+            sprites,
+            cat,
+            thumbnails,
+            snapshot,
+            level,
+            root,
+            page: root,
+            rebinding: None,
+            buttons: page_buttons(root, &ctx.settings, None),
+            selected: None,
+            started: Instant::now()
+        }
+    }
 
+    // buttons are scaled with the window, from a 800x600 layout
+    fn scale(canvas: &WindowCanvas) -> f32 {
         let (canvas_x, canvas_y) = canvas.output_size().unwrap();
-        let scale_max = (canvas_x as f32 / 800 as f32).max(canvas_y as f32 / 600 as f32);
-        let scale_min = (canvas_x as f32 / 800 as f32).min(canvas_y as f32 / 600 as f32);
-        let scale = (scale_min+scale_max)/2.0;
-        let translation_x = (canvas_x as f32 - 800.0 * scale_max) as i32 / 2;
-        let translation_y = (canvas_y as f32 - 600.0 * scale_max) as i32 / 2;
+        let scale_max = (canvas_x as f32 / 800.0).max(canvas_y as f32 / 600.0);
+        let scale_min = (canvas_x as f32 / 800.0).min(canvas_y as f32 / 600.0);
+        (scale_min+scale_max)/2.0
+    }
 
-        for event in event_pump.poll_iter() {
-            match event {
-                Event::Quit {..}
-                | Event::KeyDown { keycode: Some(Keycode::Escape), ..} => { return MenuAction::Quit },
-                Event::KeyDown { keycode: Some(Keycode::F11), ..} => {
-                    match canvas.window().fullscreen_state() {
-                        FullscreenType::Off => { canvas.window_mut().set_fullscreen(FullscreenType::True).unwrap() },
-                        _ => { canvas.window_mut().set_fullscreen(FullscreenType::Off).unwrap() }
-                    }
-                },
-                Event::MouseButtonDown { x, y, .. } => {
-                    for i in 0..buttons.len() {
-                        if buttons[i].enabled && buttons[i].rect(scale).contains_point(Point::new(x, y)) {
-                            println!("\"{}\" pressed", buttons[i].text);
-                            return buttons[i].action;
+    // move the selection to the next enabled button
+    fn step(&mut self, step: i32) {
+        let n = self.buttons.len() as i32;
+        let mut i = self.selected.map(|i| i as i32).unwrap_or(if step > 0 { -1 } else { n });
+        for _ in 0..n {
+            i = (i + step).rem_euclid(n);
+            if self.buttons[i as usize].enabled { break; }
+        }
+        self.selected = Some(i as usize);
+    }
+
+    fn back(&mut self, settings: &Settings) -> Transition<'a> {
+        match self.page {
+            // with the mouse, cancel quits right away, otherwise it moves the selection to "Quit"
+            MenuPage::Main => match self.selected {
+                None => { return Transition::Quit },
+                Some(_) => { self.selected = self.buttons.iter().position(|b| b.action == MenuAction::Quit) }
+            },
+            MenuPage::Pause => { return Transition::Pop },
+            // a confirmation opened on its own closes again
+            MenuPage::Confirm(_) if self.root == self.page => { return Transition::Pop },
+            _ => {
+                self.page = self.page.parent(self.root);
+                self.buttons = page_buttons(self.page, settings, self.rebinding);
+                self.selected = self.selected.map(|_| 0);
+            }
+        }
+        Transition::None
+    }
+
+    fn press(&mut self, ctx: &mut Context<'a>, i: usize) -> Transition<'a> {
+        let button = &self.buttons[i];
+        println!("\"{}\" pressed", button.text);
+        ctx.music_mixer.play_effect("click");
+        let (level, deaths) = self.level;
+        match button.action {
+            MenuAction::Levels => { self.page = MenuPage::Levels },
+            MenuAction::Options => {
+                // F11 may have changed fullscreen since the settings were loaded
+                ctx.settings.fullscreen = ctx.canvas.window().fullscreen_state() != FullscreenType::Off;
+                self.page = MenuPage::Options;
+            },
+            MenuAction::Controls => { self.page = MenuPage::Controls },
+            MenuAction::Back if self.page == self.root => { return Transition::Pop },
+            MenuAction::Back => { self.page = self.page.parent(self.root) },
+            MenuAction::Rebind(action) => { self.rebinding = Some(action) },
+            MenuAction::ResetBindings => {
+                ctx.settings.bindings = Default::default();
+                ctx.input.bindings = ctx.settings.bindings.clone();
+                ctx.settings.save();
+            },
+            MenuAction::Restart
+            | MenuAction::QuitToMenu if self.page == MenuPage::Pause => { self.page = MenuPage::Confirm(button.action) },
+            MenuAction::Quit => { return Transition::Quit },
+            MenuAction::NewGame => { return Transition::Clear(level::start(ctx, 0, 0)) },
+            MenuAction::Play(i) => { return Transition::Clear(level::start(ctx, i, 0)) },
+            MenuAction::Edit(i) => { return Transition::Clear(Box::new(Editor::init(ctx, i))) },
+            MenuAction::Resume => { return Transition::Pop },
+            MenuAction::Restart => { return Transition::Clear(level::start(ctx, level, deaths)) },
+            MenuAction::QuitToMenu => { return Transition::Clear(Box::new(Menu::main(ctx))) },
+            option => { change_setting(option, &mut ctx.canvas, &mut ctx.music_mixer, &mut ctx.settings) }
+        }
+        let action = self.buttons[i].action;
+        self.buttons = page_buttons(self.page, &ctx.settings, self.rebinding);
+        // keep the selection on the option being changed, otherwise start from the top
+        self.selected = self.selected.map(|_| self.buttons.iter().position(|b| b.enabled && b.action == action).unwrap_or(0));
+        // don't offer to lose progress by default
+        if let MenuPage::Confirm(_) = self.page {
+            self.selected = self.selected.map(|_| self.buttons.iter().position(|b| b.action == MenuAction::Back).unwrap());
+        }
+        Transition::None
+    }
+}
+
+impl<'a> Scene<'a> for Menu<'a> {
+    fn enter(&mut self, ctx: &mut Context<'a>) {
+        if self.root == MenuPage::Main {
+            ctx.music_mixer.play_song("slow");
+        }
+    }
+
+    fn handle_event(&mut self, ctx: &mut Context<'a>, event: &Event, actions: &[Action]) -> Transition<'a> {
+        if let Some(action) = self.rebinding {
+            match Binding::from_event(event) {
+                // escape cancels rebinding
+                Some(Binding::Key(name)) if name == Keycode::Escape.name() => {},
+                Some(binding) => {
+                    if let Err(other) = ctx.settings.bindings.rebind(action, binding.clone()) {
+                        // keep waiting for another key or button, with the conflict shown on the button
+                        self.buttons = page_buttons(self.page, &ctx.settings, self.rebinding);
+                        if let Some(button) = self.buttons.iter_mut().find(|b| b.action == MenuAction::Rebind(action)) {
+                            let text = format!("{}: {} is used by {}, press another", action.name(), binding.name(), other.name());
+                            *button = MenuButton::fitted(text, button.action, true, button.rect.x(), button.rect.y(), button.rect.height());
                         }
+                        return Transition::None;
                     }
+                    ctx.input.bindings = ctx.settings.bindings.clone();
+                    ctx.settings.save();
+                },
+                None => { return Transition::None }
+            }
+            self.rebinding = None;
+            self.buttons = page_buttons(self.page, &ctx.settings, self.rebinding);
+            return Transition::None;
+        }
+
+        let mut pressed: Option<usize> = None;
+        for &action in actions.iter() {
+            match action {
+                Action::Cancel => { return self.back(&ctx.settings) },
+                // pause closes the pause menu, or a confirmation opened over another scene
+                Action::Pause if self.snapshot.is_some() && self.page == self.root => { return Transition::Pop },
+                Action::CursorUp => { self.step(-1) },
+                Action::CursorDown => { self.step(1) },
+                Action::Confirm if self.selected.is_some() => { pressed = self.selected },
+                // keep the options page in sync with the volume hotkeys
+                Action::Mute | Action::VolumeUp | Action::VolumeDown => {
+                    self.buttons = page_buttons(self.page, &ctx.settings, self.rebinding);
                 },
                 _ => {}
             }
         }
+        match *event {
+            Event::MouseButtonDown { mouse_btn: MouseButton::Left, x, y, .. } => {
+                let (x, y) = input::to_pixels(&ctx.canvas, x, y);
+                let scale = Self::scale(&ctx.canvas);
+                pressed = self.buttons
+                    .iter()
+                    .position(|button| button.enabled && button.rect(scale).contains_point(Point::new(x, y)));
+            },
+            Event::MouseMotion {..} => { self.selected = None },
+            Event::ControllerAxisMotion { axis, value, ..} => {
+                if let Some((drow, _)) = ctx.input.controllers.stick_step(axis, value) { self.step(drow as i32) }
+            },
+            _ => {}
+        }
 
-        let (x, y) = (event_pump.mouse_state().x(), event_pump.mouse_state().y());
-        for i in 0..buttons.len() {
-            if buttons[i].rect(scale).contains_point(Point::new(x, y)) {
-                buttons[i].hovered = (buttons[i].hovered + 5).min(50);
+        match pressed {
+            Some(i) => self.press(ctx, i),
+            None => Transition::None
+        }
+    }
+
+    fn update(&mut self, ctx: &mut Context<'a>) -> Transition<'a> {
+        let scale = Self::scale(&ctx.canvas);
+        let (x, y) = input::to_pixels(&ctx.canvas, ctx.event_pump.mouse_state().x(), ctx.event_pump.mouse_state().y());
+        // hover highlight fades in and out by one step per tick
+        for i in 0..self.buttons.len() {
+            if self.buttons[i].rect(scale).contains_point(Point::new(x, y)) || self.selected == Some(i) {
+                self.buttons[i].hovered = (self.buttons[i].hovered + 1).min(50);
             }
             else {
-                buttons[i].hovered = (buttons[i].hovered - 5).max(5);
+                self.buttons[i].hovered = self.buttons[i].hovered.saturating_sub(1).max(5);
             }
         }
+        Transition::None
+    }
+
+    fn render(&mut self, ctx: &mut Context<'a>, _alpha: f32) {
+        let canvas = &mut ctx.canvas;
+        let (canvas_x, canvas_y) = canvas.output_size().unwrap();
+        let scale_max = (canvas_x as f32 / 800.0).max(canvas_y as f32 / 600.0);
+        let scale = Self::scale(canvas);
+        let translation_x = (canvas_x as f32 - 800.0 * scale_max) as i32 / 2;
+        let translation_y = (canvas_y as f32 - 600.0 * scale_max) as i32 / 2;
 
         canvas.clear();
-        
-        canvas.copy(
-            &background, 
-            None, 
-            Rect::new(
-                translation_x,
-                translation_y,
-                (800.0 * scale_max) as u32,
-                (600.0 * scale_max) as u32
-            )
-        ).unwrap();
 
-        canvas.copy_ex(
-            &cat[counter/20], 
-            None, 
-            Rect::new(
-                translation_x + (410.0 * scale_max) as i32, 
-                translation_y + (90.0 * scale_max) as i32, 
-                (400.0 * scale_max) as u32, 
-                (400.0 * scale_max) as u32
-            ), 
-            0.0, 
-            None, 
-            true, 
-            false
-        ).unwrap();
+        if let Some(snapshot) = &self.snapshot {
+            canvas.copy(snapshot, None, None).unwrap();
+            canvas.set_blend_mode(BlendMode::Blend);
+            canvas.set_draw_color(Color::RGBA(0, 0, 0, 160));
+            canvas.fill_rect(None).unwrap();
+            canvas.set_blend_mode(BlendMode::None);
+            canvas.set_draw_color(Color::BLACK);
+        }
+        else {
+            canvas.copy(
+                &self.background, 
+                None, 
+                Rect::new(
+                    translation_x,
+                    translation_y,
+                    (800.0 * scale_max) as u32,
+                    (600.0 * scale_max) as u32
+                )
+            ).unwrap();
+
+            // the cat blinks every now and then
+            self.sprites.draw(
+                canvas,
+                self.cat.frame_at(self.started.elapsed().as_millis() as u64),
+                Rect::new(
+                    translation_x + (410.0 * scale_max) as i32, 
+                    translation_y + (90.0 * scale_max) as i32, 
+                    (400.0 * scale_max) as u32, 
+                    (400.0 * scale_max) as u32
+                ),
+                true,
+                Color::WHITE
+            );
+
+            canvas.copy(
+                &self.foreground, 
+                None, 
+                Rect::new(
+                    translation_x,
+                    translation_y,
+                    (800.0 * scale_max) as u32,
+                    (600.0 * scale_max) as u32
+                )
+            ).unwrap();
+        }
 
-        canvas.copy(
-            &foreground, 
-            None, 
-            Rect::new(
-                translation_x,
-                translation_y,
-                (800.0 * scale_max) as u32,
-                (600.0 * scale_max) as u32
-            )
-        ).unwrap();
+        for i in 0..self.buttons.len() {
+            self.buttons[i].render(canvas, ctx.texture_creator, &mut ctx.font, scale);
+        }
 
-        for i in 0..buttons.len() {
-            buttons[i].render(canvas, texture_creator, font, scale);
+        if self.page == MenuPage::Levels {
+            for button in self.buttons.iter() {
+                let MenuAction::Play(i) = button.action else { continue };
+                let thumbnail = &mut self.thumbnails[i];
+                // grayed out while the level is locked
+                match button.enabled {
+                    true => { thumbnail.set_color_mod(255, 255, 255) },
+                    false => { thumbnail.set_color_mod(60, 60, 60) }
+                }
+                let query = thumbnail.query();
+                // fit the space left of the button, keeping the aspect ratio
+                let width = (query.width * THUMBNAIL_HEIGHT / query.height).min(button.rect.x() as u32 - 30);
+                let height = width * query.height / query.width;
+                let rect = Rect::new(button.rect.x() - 10 - width as i32, button.rect.center().y() - height as i32 / 2, width, height);
+                canvas.copy(thumbnail, None, MenuButton::scale_rect(rect, scale)).unwrap();
+            }
         }
+    }
 
-        counter = (counter + 1) % 40;
-        
-        canvas.present();
+    fn captures_input(&self) -> bool {
+        self.rebinding.is_some()
+    }
+}
 
-        std::thread::sleep(std::time::Duration::from_millis(100));
+// cycle the setting behind an options button, apply it right away and save
+fn change_setting(action: MenuAction, canvas: &mut WindowCanvas, music_mixer: &mut Mixer, settings: &mut Settings) {
+    match action {
+        MenuAction::Resolution => {
+            settings.next_resolution();
+            let (width, height) = settings.resolution;
+            canvas.window_mut().set_size(width, height).unwrap();
+            canvas.window_mut().set_position(WindowPos::Centered, WindowPos::Centered);
+        },
+        MenuAction::Fullscreen => {
+            settings.fullscreen = !settings.fullscreen;
+            match settings.fullscreen {
+                true => { canvas.window_mut().set_fullscreen(FullscreenType::True).unwrap() },
+                false => { canvas.window_mut().set_fullscreen(FullscreenType::Off).unwrap() }
+            }
+        },
+        MenuAction::MasterVolume => { settings.master_volume = Settings::next_volume(settings.master_volume) },
+        MenuAction::Mute => { settings.muted = !settings.muted },
+        MenuAction::MusicVolume => { settings.music_volume = Settings::next_volume(settings.music_volume) },
+        MenuAction::EffectsVolume => { settings.effects_volume = Settings::next_volume(settings.effects_volume) },
+        MenuAction::AnimationSpeed => { settings.next_animation_speed() },
+        MenuAction::PixelPerfect => { settings.pixel_perfect = !settings.pixel_perfect },
+        MenuAction::Letterbox => { settings.letterbox = !settings.letterbox },
+        _ => {}
+    }
+    volume::apply(settings, music_mixer);
+    if action == MenuAction::EffectsVolume {
+        music_mixer.play_effect("win");
     }
-}
\ No newline at end of file
+    settings.save();
+}
+
+// small isometric picture of a level's tilemap, one diamond per tile
+fn level_thumbnail<'a>(texture_creator: &'a TextureCreator<WindowContext>, name: &str) -> Texture<'a> {
+    let level = loader::load_level(format!("resources/levels/{name}.json"));
+    let mut map = Map::new();
+    map.load(level.tilemap);
+    let rows = map.tiles.len() as i32;
+    let cols = map.tiles.iter().map(|row| row.len()).max().unwrap_or(0) as i32;
+
+    let mut surface = Surface::new(((rows + cols) * 2) as u32, (rows + cols + 2) as u32, PixelFormatEnum::RGBA8888).unwrap();
+    for (row, tiles) in map.tiles.iter().enumerate() {
+        for (col, tile) in tiles.iter().enumerate() {
+            let (x, y) = ((col as i32 - row as i32 + rows - 1) * 2, row as i32 + col as i32 + 2);
+            let rect = match (tile, (row, col)) {
+                (TileType::None, _) => continue,
+                (_, pos) if pos == level.exit => (Rect::new(x, y, 4, 2), Color::RGB(153, 229, 80)),
+                (_, pos) if pos == level.player => (Rect::new(x, y, 4, 2), Color::RGB(223, 113, 38)),
+                (TileType::Floor, _) => (Rect::new(x, y, 4, 2), Color::RGB(89, 86, 82)),
+                (TileType::Liquid, _) => (Rect::new(x, y, 4, 2), Color::RGB(63, 63, 116)),
+                // walls stick up a little
+                (TileType::Wall, _) => (Rect::new(x, y - 2, 4, 4), Color::RGB(155, 173, 183))
+            };
+            surface.fill_rect(rect.0, rect.1).unwrap();
+        }
+    }
+    texture_creator.create_texture_from_surface(&surface).map_err(|e| e.to_string()).unwrap()
+}
+
+fn page_buttons(page: MenuPage, settings: &Settings, rebinding: Option<Action>) -> Vec<MenuButton> {
+    match page {
+        MenuPage::Main => {
+            let mut buttons = vec![
+                MenuButton::new(
+                    "New Game".to_string(), 
+                    MenuAction::NewGame, 
+                    true,
+                    Rect::new(0, 20, 340, 60)
+                ),
+                MenuButton::new(
+                    "Levels".to_string(), 
+                    MenuAction::Levels, 
+                    true,
+                    Rect::new(0, 100, 300, 60)
+                ),
+                MenuButton::new(
+                    "Options".to_string(), 
+                    MenuAction::Options, 
+                    true,
+                    Rect::new(0, 180, 320, 60)
+                ),
+                MenuButton::new(
+                    "Quit".to_string(), 
+                    MenuAction::Quit, 
+                    true,
+                    Rect::new(0, 260, 260, 60)
+                )
+            ];
+            // the level editor is a debug tool, it goes above "Quit"
+            if settings.debug {
+                buttons.insert(3, MenuButton::new("Editor".to_string(), MenuAction::Edit(0), true, Rect::new(0, 260, 300, 60)));
+                buttons[4].rect = Rect::new(0, 340, 260, 60);
+            }
+            buttons
+        },
+        MenuPage::Pause => {
+            let options = [
+                ("Resume", MenuAction::Resume),
+                ("Restart level", MenuAction::Restart),
+                ("Options", MenuAction::Options),
+                ("Quit to menu", MenuAction::QuitToMenu)
+            ];
+            options
+                .into_iter()
+                .enumerate()
+                .map(|(i, (text, action))| MenuButton::fitted(text.to_string(), action, true, 0, 20 + 80 * i as i32, 60))
+                .collect()
+        },
+        MenuPage::Confirm(action) => {
+            let question = match action {
+                MenuAction::Restart => "Restart level?",
+                MenuAction::Edit(_) => "Open another level?",
+                _ => "Quit to menu?"
+            };
+            vec![
+                MenuButton::fitted(question.to_string(), action, false, 0, 20, 60),
+                MenuButton::fitted("Progress will be lost".to_string(), action, false, 0, 90, 40),
+                MenuButton::fitted("Yes".to_string(), action, true, 0, 160, 60),
+                MenuButton::fitted("No".to_string(), MenuAction::Back, true, 0, 240, 60)
+            ]
+        },
+        MenuPage::Options => {
+            let on_off = |value: bool| if value { "On" } else { "Off" };
+            let options = [
+                (format!("Resolution {}x{}", settings.resolution.0, settings.resolution.1), MenuAction::Resolution),
+                (format!("Fullscreen {}", on_off(settings.fullscreen)), MenuAction::Fullscreen),
+                (format!("Volume {}%", settings.master_volume), MenuAction::MasterVolume),
+                (format!("Mute {}", on_off(settings.muted)), MenuAction::Mute),
+                (format!("Music {}%", settings.music_volume), MenuAction::MusicVolume),
+                (format!("Effects {}%", settings.effects_volume), MenuAction::EffectsVolume),
+                (format!("Animation speed {}", settings.animation_speed), MenuAction::AnimationSpeed),
+                (format!("Pixel perfect {}", on_off(settings.pixel_perfect)), MenuAction::PixelPerfect),
+                (format!("Letterbox {}", on_off(settings.letterbox)), MenuAction::Letterbox),
+                ("Controls".to_string(), MenuAction::Controls),
+                ("Back".to_string(), MenuAction::Back)
+            ];
+            options
+                .into_iter()
+                .enumerate()
+                .map(|(i, (text, action))| MenuButton::fitted(text, action, true, 0, 20 + 52 * i as i32, 44))
+                .collect()
+        },
+        MenuPage::Controls => {
+            let mut buttons: Vec<MenuButton> = ACTIONS
+                .iter()
+                .enumerate()
+                .map(|(i, &action)| {
+                    let keys = match rebinding == Some(action) {
+                        true => "press a key or button".to_string(),
+                        false => settings.bindings.get(action).iter().map(|b| b.name()).collect::<Vec<String>>().join(", ")
+                    };
+                    MenuButton::fitted(format!("{}: {}", action.name(), keys), MenuAction::Rebind(action), true, 0, 10 + 32 * i as i32, 28)
+                })
+                .collect();
+            let y = 20 + 32 * ACTIONS.len() as i32;
+            buttons.push(MenuButton::fitted("Reset".to_string(), MenuAction::ResetBindings, true, 0, y, 28));
+            buttons.push(MenuButton::fitted("Back".to_string(), MenuAction::Back, true, 0, y + 32, 28));
+            buttons
+        },
+        MenuPage::Levels => {
+            let scores = Scores::load();
+            let mut buttons: Vec<MenuButton> = LEVELS
+                .iter()
+                .enumerate()
+                .map(|(i, name)| {
+                    let unlocked = settings.debug || scores.unlocked(&LEVELS, i);
+                    let best = match scores.best(name) {
+                        Some(stats) => format!("{} {}", stats.stars_text(), stats.score()),
+                        None if unlocked => "---".to_string(),
+                        None => "locked".to_string()
+                    };
+                    MenuButton::fitted(format!("{name}  {best}"), MenuAction::Play(i), unlocked, THUMBNAIL_WIDTH as i32, 50 + 120 * i as i32, 40)
+                })
+                .collect();
+            buttons.push(MenuButton::fitted("Back".to_string(), MenuAction::Back, true, 0, 45 + 120 * LEVELS.len() as i32, 50));
+            buttons
+        }
+    }
+}
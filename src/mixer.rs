@@ -1,13 +1,55 @@
 use std::collections::HashMap;
+use serde::Deserialize;
 use sdl2::mixer::*;
 
+// lists every track and effect, files are relative to the manifest
+const MANIFEST: &str = "resources/audio/manifest.json";
+
+const EFFECT_CHANNELS: i32 = 6;
+
+// switching tracks fades the old one out, then the new one in
+const FADE_OUT_MS: i32 = 800;
+const FADE_IN_MS: i32 = 1200;
+
+// volume (0-255) left in the far ear for a sound panned all the way to one side
+const MIN_PAN_VOLUME: f32 = 60.0;
+// SDL distance (0-255) of the farthest sounds, below 255 so they never go silent
+const MAX_DISTANCE: f32 = 180.0;
+
+#[derive(Deserialize)]
+struct AudioAsset {
+    file: String,
+    // volume from 0 to 128, before the music/effects volume settings are applied
+    #[serde(default = "default_asset_volume")]
+    volume: i32
+}
+
+fn default_asset_volume() -> i32 { MAX_VOLUME }
+
+#[derive(Deserialize, Default)]
+struct AudioManifest {
+    #[serde(default)]
+    music: HashMap<String, AudioAsset>,
+    #[serde(default)]
+    effects: HashMap<String, AudioAsset>
+}
+
+// music is streamed from disk (MP3 or OGG), effects are decoded into memory up front;
+// without an audio device this is a silent mixer: nothing is loaded and every call does nothing
 pub struct Mixer {
-    _mixer_context: Sdl2MixerContext,
-    pub chunks: HashMap <String, Chunk>,
-    music_channels: HashMap <i32, Channel>,
+    _mixer_context: Option<Sdl2MixerContext>,
+    // tracks with their volume from the manifest
+    tracks: HashMap <String, (Music<'static>, i32)>,
+    chunks: HashMap <String, Chunk>,
+    // volumes from 0 to 128 for the music and effects buses, master volume included
+    music_volume: i32,
+    effects_volume: i32,
     current_track: Option<String>,
-    current_music_channel: i32,
-    other_channels: HashMap <String, Channel>
+    // track waiting for the previous one to fade out
+    pending_track: Option<String>,
+    effect_channels: Vec<Channel>,
+    // channel to take over when all of them are busy
+    next_effect_channel: usize
 }
 
 impl Mixer {
@@ -17,61 +59,159 @@ impl Mixer {
         let format = sdl2::sys::AUDIO_S16LSB; // signed 16 bit samples, in little-endian byte order
         let channels = DEFAULT_CHANNELS; // Stereo
         let chunk_size = 1_024;
-        open_audio(frequency, format as u16, channels, chunk_size).unwrap();
-        allocate_channels(4);
+        if let Err(e) = open_audio(frequency, format as u16, channels, chunk_size) {
+            println!("Failed to open audio device, playing without sound: {e}");
+            return Self::null();
+        }
+        allocate_channels(EFFECT_CHANNELS);
+
+        // succeeds if at least one of the formats is available, files in the other fail to load below
+        let context = match sdl2::mixer::init(InitFlag::MP3 | InitFlag::OGG) {
+            Ok(context) => context,
+            Err(e) => {
+                println!("Failed to initialize the audio mixer, playing without sound: {e}");
+                close_audio();
+                return Self::null();
+            }
+        };
 
-        let _mixer_context = sdl2::mixer::init(InitFlag::MP3).unwrap();
+        let manifest: AudioManifest = match std::fs::read_to_string(MANIFEST).map_err(|e| e.to_string())
+            .and_then(|source| serde_json::from_str(&source).map_err(|e| e.to_string())) {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                println!("Failed to load audio manifest '{MANIFEST}': {e}");
+                AudioManifest::default()
+            }
+        };
+        let directory = std::path::Path::new(MANIFEST).parent().unwrap();
 
+        let mut tracks: HashMap<String, (Music<'static>, i32)> = HashMap::new();
+        for (name, asset) in manifest.music.iter() {
+            match Music::from_file(directory.join(&asset.file)) {
+                Ok(music) => { tracks.insert(name.clone(), (music, asset.volume)); },
+                Err(e) => { println!("Failed to load track '{}' from '{}': {e}", name, asset.file) }
+            }
+        }
         let mut chunks: HashMap<String, Chunk> = HashMap::new();
-        chunks.insert("slow".to_string(), Chunk::from_file("resources/audio/slowversion-01.mp3").unwrap());
-        let mut fast_chunk = Chunk::from_file("resources/audio/fastversion-01.mp3").unwrap();
-        fast_chunk.set_volume(24);
-        chunks.insert("fast".to_string(), fast_chunk);
-        chunks.insert("win".to_string(), Chunk::from_file("resources/audio/win-01.mp3").unwrap());
-
-        let mut music_channels: HashMap <i32, Channel> = HashMap::new();
-        music_channels.insert(1, Channel::all().to_owned());
-        music_channels.insert(2, Channel::all().to_owned());
-        let current_track = None;
-        let current_music_channel: i32 = 2;
-
-        let mut other_channels: HashMap <String, Channel> = HashMap::new();
-        other_channels.insert("effects".to_string(), Channel::all().to_owned());
-
-        Self { _mixer_context, chunks, music_channels, current_track, current_music_channel, other_channels }
+        for (name, asset) in manifest.effects.iter() {
+            match Chunk::from_file(directory.join(&asset.file)) {
+                Ok(mut chunk) => {
+                    chunk.set_volume(asset.volume);
+                    chunks.insert(name.clone(), chunk);
+                },
+                Err(e) => { println!("Failed to load sound '{}' from '{}': {e}", name, asset.file) }
+            }
+        }
+
+        let effect_channels: Vec<Channel> = (0..EFFECT_CHANNELS).map(Channel).collect();
+
+        Self {
+            _mixer_context: Some(context),
+            tracks,
+            chunks,
+            music_volume: MAX_VOLUME,
+            effects_volume: MAX_VOLUME,
+            current_track: None,
+            pending_track: None,
+            effect_channels,
+            next_effect_channel: 0
+        }
+    }
+
+    pub fn null() -> Self {
+        Self {
+            _mixer_context: None,
+            tracks: HashMap::new(),
+            chunks: HashMap::new(),
+            music_volume: MAX_VOLUME,
+            effects_volume: MAX_VOLUME,
+            current_track: None,
+            pending_track: None,
+            effect_channels: vec![],
+            next_effect_channel: 0
+        }
     }
 
+    // set the volume buses, in percent; tracks and chunks keep their own volume from the manifest
+    pub fn set_volume(&mut self, master: u32, music: u32, effects: u32) {
+        self.music_volume = MAX_VOLUME * (master * music) as i32 / 10_000;
+        self.effects_volume = MAX_VOLUME * (master * effects) as i32 / 10_000;
+        if let Some((_, volume)) = self.current_track.as_ref().and_then(|name| self.tracks.get(name)) {
+            Music::set_volume(self.music_volume * volume / MAX_VOLUME);
+        }
+        for channel in self.effect_channels.iter() {
+            channel.set_volume(self.effects_volume);
+        }
+    }
+
+    // switch to another track, the new one starts once the old one has faded out (see `update`)
     pub fn play_song(&mut self, name: &str) {
-        if self.current_track.is_some() && name.to_string().eq(self.current_track.as_mut().unwrap()) {
+        if self.current_track.as_deref() == Some(name) || !self.tracks.contains_key(name) {
             return;
         }
         self.current_track = Some(name.to_string());
-        if self.music_channels.get(&self.current_music_channel).unwrap().is_playing() {
-            println!("Stopping music on channel {}", self.current_music_channel);
-            self.music_channels.get(&self.current_music_channel).unwrap().fade_out(3000);
+        if Music::is_playing() {
+            println!("Fading out music");
+            Music::fade_out(FADE_OUT_MS).ok();
+            self.pending_track = Some(name.to_string());
+        }
+        else {
+            self.start_track(name);
+        }
+    }
+
+    // has to be called every frame by screens that change the music
+    pub fn update(&mut self) {
+        if self.pending_track.is_some() && !Music::is_playing() {
+            let name = self.pending_track.take().unwrap();
+            self.start_track(&name);
+        }
+    }
+
+    fn start_track(&mut self, name: &str) {
+        let (music, volume) = &self.tracks[name];
+        println!("Playing {name}");
+        Music::set_volume(self.music_volume * volume / MAX_VOLUME);
+        if let Err(e) = music.fade_in(-1, FADE_IN_MS) {
+            println!("Failed to play {name}: {e}");
         }
-        self.current_music_channel = 3 - self.current_music_channel;
-        println!("Playing {} on channel {}", name, self.current_music_channel);
-        self.music_channels.get(&self.current_music_channel).unwrap().fade_in(
-            self.chunks.get(&name.to_string()).unwrap(), 
-            -1, 
-            1500
-        ).unwrap();
     }
 
     pub fn stop_music(&mut self) {
-        if self.music_channels.get(&self.current_music_channel).unwrap().is_playing() {
-            println!("Stopping music on channel {}", self.current_music_channel);
-            self.music_channels.get(&self.current_music_channel).unwrap().fade_out(3000);
-            self.current_track = None;
+        self.pending_track = None;
+        if self.current_track.take().is_some() && Music::is_playing() {
+            println!("Stopping music");
+            Music::fade_out(3000).ok();
         }
     }
 
     pub fn play_effect(&mut self, name: &str) {
-        self.other_channels.get("effects").unwrap().fade_in(
-            self.chunks.get(&name.to_string()).unwrap(), 
-            0,
-            150
-        ).unwrap();
+        self.play_effect_at(name, (0.0, 0.0));
+    }
+
+    // play on a free effect channel, so overlapping sounds don't cut each other off;
+    // if all are busy, they are taken over in turn.
+    // pan goes from -1 (left) to 1 (right), distance from 0 (right here) to 1 (far away, but still audible)
+    pub fn play_effect_at(&mut self, name: &str, (pan, distance): (f32, f32)) {
+        let Some(chunk) = self.chunks.get(name) else { return };
+        let channel = match self.effect_channels.iter().find(|channel| !channel.is_playing()) {
+            Some(&channel) => channel,
+            None => {
+                let channel = self.effect_channels[self.next_effect_channel];
+                self.next_effect_channel = (self.next_effect_channel + 1) % self.effect_channels.len();
+                channel
+            }
+        };
+        channel.set_volume(self.effects_volume);
+        // full volume on both sides and no distance turn the effects off again
+        let side = |amount: f32| (255.0 - (255.0 - MIN_PAN_VOLUME) * amount.clamp(0.0, 1.0)) as u8;
+        let positioned = channel.set_panning(side(pan), side(-pan))
+            .and_then(|_| channel.set_distance((distance.clamp(0.0, 1.0) * MAX_DISTANCE) as u8));
+        if let Err(e) = positioned {
+            println!("Failed to position {name}: {e}");
+        }
+        if let Err(e) = channel.play(chunk, 0) {
+            println!("Failed to play {name}: {e}");
+        }
     }
 }
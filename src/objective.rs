@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+
+use crate::level::State;
+
+// optional per-level goals, listed under "objectives" in the level file, e.g.
+// [{"type": "collect_all"}, {"type": "undetected"}, {"type": "max_turns", "turns": 40}]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Objective {
+    CollectAll,
+    Undetected,
+    MaxTurns { turns: u32 }
+}
+
+impl Objective {
+    pub fn description(&self) -> String {
+        match self {
+            Objective::CollectAll => "Collect everything".to_string(),
+            Objective::Undetected => "Never be detected".to_string(),
+            Objective::MaxTurns { turns } => format!("Finish in {turns} turns"),
+        }
+    }
+
+    pub fn completed(&self, state: &State) -> bool {
+        match self {
+            Objective::CollectAll => state.collectibles.iter().all(|c| c.collected),
+            Objective::Undetected => state.times_detected == 0,
+            Objective::MaxTurns { turns } => state.turns <= *turns,
+        }
+    }
+}
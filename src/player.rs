@@ -1,18 +1,19 @@
 use crate::entity::*;
+use crate::animation::Animation;
 
 #[derive(Clone)]
 pub struct Player {
     pub pos: (usize, usize),
-    pub current_sprite: String,
-    pub flipped: bool
+    pub flipped: bool,
+    pub animation: Option<Animation>
 }
 
 impl Player {
     pub fn init(pos: (usize, usize)) -> Self {
         Self {
             pos: pos, 
-            current_sprite: "cat_idle_1".to_string(),
-            flipped: false
+            flipped: false,
+            animation: None
         }
     } 
 }
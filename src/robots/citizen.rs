@@ -3,6 +3,7 @@
 
 use crate::entity::*;
 use crate::level::State;
+use crate::animation::Animation;
 
 extern crate rand;
 use rand::Rng;
@@ -13,17 +14,19 @@ pub enum CitizenState { CALM, PANIC }
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Citizen {
     pos: (usize, usize),
-    pub mode: CitizenState
+    pub mode: CitizenState,
+    pub flipped: bool,
+    pub animation: Option<Animation>
 }
 
 impl Citizen {
     pub fn init(pos: (usize, usize), mode: CitizenState) -> Self {
-        Self {pos, mode}
+        Self {pos, mode, flipped: false, animation: None}
     } 
     
     pub fn turn(&mut self, state: &State) -> Vec<(usize, usize)> {
         let player_pos = state.player.get_position();
-        let sees = self.sees(player_pos, &state.tilemap.tiles);
+        let sees = self.sees_in_light(player_pos, &state.tilemap.tiles, state.lights.visibility(player_pos));
         
         match sees {
             true => {
@@ -7,6 +7,7 @@ use queues::*;
 use crate::entity::*;
 use crate::{map::TileType, level::State};
 use crate::robots::citizen::*;
+use crate::animation::Animation;
 
 extern crate rand;
 use rand::Rng;
@@ -15,13 +16,20 @@ use rand::Rng;
 pub struct Commando {
     pos: (usize, usize),
     chasing: bool,
-    chase_pos: Option<(usize, usize)>
+    chase_pos: Option<(usize, usize)>,
+    pub flipped: bool,
+    pub animation: Option<Animation>
 }
 
 impl Commando {
     pub fn init(pos: (usize, usize)) -> Self {
-        Self {pos, chasing: false, chase_pos: None}
+        Self {pos, chasing: false, chase_pos: None, flipped: false, animation: None}
     } 
+
+    // still looking for the cat, even if it's out of sight
+    pub fn chasing(&self) -> bool {
+        self.chasing
+    }
     
     pub fn turn(&mut self, state: &State) -> Vec<(usize, usize)> {
         let player_pos = state.player.get_position();
@@ -35,7 +43,7 @@ impl Commando {
         }
         
         // if I see player, chase!
-        let sees_player = self.sees(player_pos, &state.tilemap.tiles);
+        let sees_player = self.sees_in_light(player_pos, &state.tilemap.tiles, state.lights.visibility(player_pos));
         if sees_player {
             println!(" Apprehending suspect!");
             let player_pos = state.player.get_position();
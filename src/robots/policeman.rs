@@ -7,18 +7,21 @@ use queues::*;
 use crate::entity::*;
 use crate::{map::TileType, level::State};
 use crate::robots::citizen::*;
+use crate::animation::Animation;
 
 extern crate rand;
 use rand::Rng;
 
 #[derive(Clone)]
 pub struct Policeman {
-    pos: (usize, usize)
+    pos: (usize, usize),
+    pub flipped: bool,
+    pub animation: Option<Animation>
 }
 
 impl Policeman {
     pub fn init(pos: (usize, usize)) -> Self {
-        Self {pos}
+        Self {pos, flipped: false, animation: None}
     } 
     
     pub fn turn(&mut self, state: &State) -> Vec<(usize, usize)> {
@@ -26,7 +29,7 @@ impl Policeman {
         let citizens = &state.citizens;
         
         // if I see player, chase!
-        let sees_player = self.sees(player_pos, &state.tilemap.tiles);
+        let sees_player = self.sees_in_light(player_pos, &state.tilemap.tiles, state.lights.visibility(player_pos));
         if sees_player {
             println!(" Apprehending suspect!");
             let player_pos = state.player.get_position();
@@ -0,0 +1,200 @@
+use std::time::{Duration, Instant};
+use sdl2::EventPump;
+use sdl2::event::Event;
+use sdl2::pixels::Color;
+use sdl2::render::{BlendMode, TextureCreator, WindowCanvas};
+use sdl2::ttf::Font;
+use sdl2::video::{FullscreenType, WindowContext};
+
+use crate::clock::Clock;
+use crate::input::{Action, Input};
+use crate::mixer::Mixer;
+use crate::settings::Settings;
+use crate::volume::{self, Indicator};
+
+// how long the screen takes to fade to black when switching scenes, and the same again to fade back in
+const FADE: Duration = Duration::from_millis(250);
+
+// everything the scenes share, owned by `main` for the whole game
+pub struct Context<'a> {
+    pub canvas: WindowCanvas,
+    pub texture_creator: &'a TextureCreator<WindowContext>,
+    pub event_pump: EventPump,
+    pub input: Input,
+    pub font: Font<'a, 'static>,
+    pub music_mixer: Mixer,
+    pub settings: Settings,
+    // paced by `SceneStack::run` only, scenes get its ticks through `update` and its alpha through `render`
+    pub clock: Clock,
+    pub indicator: Indicator<'a>
+}
+
+pub type SceneBox<'a> = Box<dyn Scene<'a> + 'a>;
+
+pub enum Transition<'a> {
+    None,
+    // open a scene on top of this one, e.g. the pause menu; this one keeps its state
+    Push(SceneBox<'a>),
+    // close this scene and go back to the one below
+    Pop,
+    // switch to another scene, fading through black
+    Replace(SceneBox<'a>),
+    // close every scene and switch to another one, fading through black
+    Clear(SceneBox<'a>),
+    Quit
+}
+
+// a screen of the game: splash, menu, level, pause menu, summary...
+pub trait Scene<'a> {
+    // called once, when the scene is first shown
+    fn enter(&mut self, _ctx: &mut Context<'a>) {}
+
+    // called when the scene on top of this one is closed
+    fn resume(&mut self, _ctx: &mut Context<'a>) {}
+
+    // called for every event, with the actions it is bound to
+    fn handle_event(&mut self, ctx: &mut Context<'a>, event: &Event, actions: &[Action]) -> Transition<'a>;
+
+    // called once per clock tick
+    fn update(&mut self, ctx: &mut Context<'a>) -> Transition<'a>;
+
+    // draw the current frame without presenting it, alpha is how far it is between the last two ticks
+    fn render(&mut self, ctx: &mut Context<'a>, alpha: f32);
+
+    // while true, the scene gets every event without actions and the global hotkeys are off,
+    // e.g. while waiting for a new key binding
+    fn captures_input(&self) -> bool { false }
+}
+
+// open scenes, only the topmost one gets events, updates and drawn
+pub struct SceneStack<'a> {
+    scenes: Vec<SceneBox<'a>>,
+    // transition waiting for the screen to fade out
+    pending: Option<Transition<'a>>,
+    // when the last fade out or fade in started
+    fade_started: Instant
+}
+
+impl<'a> SceneStack<'a> {
+    pub fn init(first: SceneBox<'a>) -> Self {
+        Self { scenes: vec![first], pending: None, fade_started: Instant::now() }
+    }
+
+    // run until a scene quits, the window is closed or the last scene is popped
+    pub fn run(&mut self, ctx: &mut Context<'a>) {
+        self.scenes.last_mut().unwrap().enter(ctx);
+        ctx.clock.reset();
+
+        loop {
+            let ticks = ctx.clock.frame();
+            ctx.music_mixer.update();
+
+            let events: Vec<Event> = ctx.event_pump.poll_iter().collect();
+            for event in events.iter() {
+                if let Event::Quit {..} = event { return }
+                let mut actions = ctx.input.handle_event(event);
+                if self.scenes.last().unwrap().captures_input() {
+                    actions.clear();
+                }
+                for &action in actions.iter() {
+                    global_action(ctx, action);
+                }
+                // input is ignored while fading out
+                if self.pending.is_some() { continue }
+                let transition = self.scenes.last_mut().unwrap().handle_event(ctx, event, &actions);
+                if !self.apply(ctx, transition) { return }
+            }
+
+            for _ in 0..ticks {
+                if self.pending.is_some() { break }
+                let transition = self.scenes.last_mut().unwrap().update(ctx);
+                if !self.apply(ctx, transition) { return }
+            }
+
+            // switch once the screen is black, then fade the new scene in
+            if self.pending.is_some() && self.fade_started.elapsed() >= FADE {
+                let transition = self.pending.take().unwrap();
+                if !self.switch(ctx, transition) { return }
+                self.fade_started = Instant::now();
+            }
+
+            let alpha = ctx.clock.alpha();
+            self.scenes.last_mut().unwrap().render(ctx, alpha);
+            self.render_fade(&mut ctx.canvas);
+            ctx.indicator.render(&mut ctx.canvas);
+            ctx.canvas.present();
+        }
+    }
+
+    // returns false when the game should quit
+    fn apply(&mut self, ctx: &mut Context<'a>, transition: Transition<'a>) -> bool {
+        match transition {
+            Transition::None => true,
+            // pushing and popping overlays like the pause menu happens right away
+            Transition::Push(_) | Transition::Pop | Transition::Quit => self.switch(ctx, transition),
+            Transition::Replace(_) | Transition::Clear(_) => {
+                self.pending = Some(transition);
+                self.fade_started = Instant::now();
+                true
+            }
+        }
+    }
+
+    fn switch(&mut self, ctx: &mut Context<'a>, transition: Transition<'a>) -> bool {
+        let mut scene = match transition {
+            Transition::None => { return true },
+            Transition::Quit => { return false },
+            Transition::Pop => {
+                self.scenes.pop();
+                let Some(below) = self.scenes.last_mut() else { return false };
+                below.resume(ctx);
+                return true;
+            },
+            Transition::Push(scene) => scene,
+            Transition::Replace(scene) => {
+                self.scenes.pop();
+                scene
+            },
+            Transition::Clear(scene) => {
+                self.scenes.clear();
+                scene
+            }
+        };
+        scene.enter(ctx);
+        self.scenes.push(scene);
+        // loading the new scene shouldn't count as time to catch up on
+        ctx.clock.reset();
+        true
+    }
+
+    fn render_fade(&self, canvas: &mut WindowCanvas) {
+        let progress = (self.fade_started.elapsed().as_secs_f32() / FADE.as_secs_f32()).min(1.0);
+        let darkness = match self.pending {
+            Some(_) => progress,
+            None => 1.0 - progress
+        };
+        if darkness <= 0.0 { return }
+        canvas.set_blend_mode(BlendMode::Blend);
+        canvas.set_draw_color(Color::RGBA(0, 0, 0, (255.0 * darkness) as u8));
+        canvas.fill_rect(None).unwrap();
+        canvas.set_blend_mode(BlendMode::None);
+        canvas.set_draw_color(Color::BLACK);
+    }
+}
+
+// hotkeys that work on every screen
+fn global_action(ctx: &mut Context, action: Action) {
+    match action {
+        Action::ToggleFullscreen => {
+            match ctx.canvas.window().fullscreen_state() {
+                FullscreenType::Off => { ctx.canvas.window_mut().set_fullscreen(FullscreenType::True).unwrap() },
+                _ => { ctx.canvas.window_mut().set_fullscreen(FullscreenType::Off).unwrap() }
+            }
+        },
+        action => {
+            if volume::handle_action(action, &mut ctx.settings, &mut ctx.music_mixer) {
+                ctx.indicator.show(ctx.texture_creator, &mut ctx.font, &ctx.settings);
+            }
+        }
+    }
+}
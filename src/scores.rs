@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+
+// how a single run through a level went
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct LevelStats {
+    pub turns: u32,
+    pub times_detected: u32,
+    pub deaths: u32,
+    pub seconds: u32,
+    pub collected: u32
+}
+
+impl LevelStats {
+    pub fn score(&self) -> u32 {
+        let bonus = 1000 + 100 * self.collected as i32;
+        let penalty = 10 * self.turns + 100 * self.times_detected + 200 * self.deaths + self.seconds;
+        (bonus - penalty as i32).max(0) as u32
+    }
+
+    // one star for escaping, one for never dying, one for never being detected
+    pub fn stars(&self) -> u32 {
+        1 + (self.deaths == 0) as u32 + (self.times_detected == 0) as u32
+    }
+
+    pub fn stars_text(&self) -> String {
+        "*".repeat(self.stars() as usize) + &"-".repeat(3 - self.stars() as usize)
+    }
+}
+
+// best results per level, stored in the user's data directory
+#[derive(Default, Serialize, Deserialize)]
+pub struct Scores {
+    levels: HashMap<String, LevelStats>
+}
+
+impl Scores {
+    fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("pipaluk").join("scores.json"))
+    }
+
+    pub fn load() -> Self {
+        Self::path()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|source| serde_json::from_str(&source).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        let Some(path) = Self::path() else { return };
+        let result = std::fs::create_dir_all(path.parent().unwrap())
+            .and_then(|_| std::fs::write(&path, serde_json::to_string_pretty(self).unwrap()));
+        if let Err(e) = result {
+            println!("Failed to save scores to {}: {e}", path.display());
+        }
+    }
+
+    pub fn best(&self, level: &str) -> Option<&LevelStats> {
+        self.levels.get(level)
+    }
+
+    // every finished level gets a result, so this doubles as campaign progress
+    pub fn completed(&self, level: &str) -> bool {
+        self.levels.contains_key(level)
+    }
+
+    // the first level is always open, the others once the one before them is beaten
+    pub fn unlocked(&self, levels: &[&str], index: usize) -> bool {
+        index == 0 || self.completed(levels[index - 1])
+    }
+
+    // keep the result if it beats the previous best, returns true if it did
+    pub fn submit(&mut self, level: &str, stats: LevelStats) -> bool {
+        match self.levels.get(level) {
+            Some(best) if best.score() >= stats.score() => false,
+            _ => {
+                self.levels.insert(level.to_string(), stats);
+                true
+            }
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use std::path::PathBuf;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+
+use crate::input::Bindings;
+
+pub const RESOLUTIONS: [(u32, u32); 5] = [(800, 600), (1024, 768), (1280, 720), (1600, 900), (1920, 1080)];
+pub const VOLUME_STEP: u32 = 25;
+// master volume change per press of the volume hotkeys
+pub const MASTER_VOLUME_STEP: u32 = 10;
+pub const MAX_ANIMATION_SPEED: u32 = 5;
+
+// user preferences, stored in the user's config directory
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub resolution: (u32, u32),
+    pub fullscreen: bool,
+    // volumes in percent, music and effects are scaled by the master volume
+    pub master_volume: u32,
+    pub music_volume: u32,
+    pub effects_volume: u32,
+    pub muted: bool,
+    // 1 (slowest) to MAX_ANIMATION_SPEED (fastest)
+    pub animation_speed: u32,
+    // scale the world by whole numbers only, sharper but it fits the window less well
+    pub pixel_perfect: bool,
+    // always show the same area of the level, with black bars where the window's shape doesn't match
+    pub letterbox: bool,
+    // wait for the display's refresh when presenting frames, applied at startup
+    pub vsync: bool,
+    // show the whole map, print debug info and enable Ctrl+W/Ctrl+L shortcuts
+    pub debug: bool,
+    pub bindings: Bindings
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            resolution: RESOLUTIONS[0],
+            fullscreen: false,
+            master_volume: 100,
+            music_volume: 100,
+            effects_volume: 100,
+            muted: false,
+            animation_speed: 3,
+            pixel_perfect: false,
+            letterbox: false,
+            vsync: true,
+            debug: false,
+            bindings: Bindings::default()
+        }
+    }
+}
+
+impl Settings {
+    fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("pipaluk").join("settings.json"))
+    }
+
+    pub fn load() -> Self {
+        let mut settings: Settings = Self::path()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|source| serde_json::from_str(&source).ok())
+            .unwrap_or_default();
+        settings.master_volume = settings.master_volume.min(100);
+        settings.music_volume = settings.music_volume.min(100);
+        settings.effects_volume = settings.effects_volume.min(100);
+        settings.animation_speed = settings.animation_speed.clamp(1, MAX_ANIMATION_SPEED);
+        settings.bindings.fill_defaults();
+        settings
+    }
+
+    pub fn save(&self) {
+        let Some(path) = Self::path() else { return };
+        let result = std::fs::create_dir_all(path.parent().unwrap())
+            .and_then(|_| std::fs::write(&path, serde_json::to_string_pretty(self).unwrap()));
+        if let Err(e) = result {
+            println!("Failed to save settings to {}: {e}", path.display());
+        }
+    }
+
+    // time the cat spends moving between two tiles
+    pub fn tile_duration(&self) -> Duration {
+        Duration::from_millis(50) * (MAX_ANIMATION_SPEED + 1 - self.animation_speed)
+    }
+
+    pub fn next_resolution(&mut self) {
+        let current = RESOLUTIONS.iter().position(|&r| r == self.resolution).unwrap_or(RESOLUTIONS.len() - 1);
+        self.resolution = RESOLUTIONS[(current + 1) % RESOLUTIONS.len()];
+    }
+
+    // cycle up in steps, back to 0 after 100
+    pub fn next_volume(volume: u32) -> u32 {
+        match volume {
+            100.. => 0,
+            v => (v + VOLUME_STEP).min(100)
+        }
+    }
+
+    // master volume as it should be played, 0 while muted
+    pub fn master(&self) -> u32 {
+        match self.muted {
+            true => 0,
+            false => self.master_volume
+        }
+    }
+
+    pub fn next_animation_speed(&mut self) {
+        self.animation_speed = self.animation_speed % MAX_ANIMATION_SPEED + 1;
+    }
+}
@@ -1,65 +1,80 @@
 extern crate sdl2;
 
-use sdl2::keyboard::Keycode;
-use sdl2::ttf::Font;
-use sdl2::EventPump;
 use sdl2::pixels::Color;
-use sdl2::render::{ WindowCanvas, TextureCreator};
-use sdl2::video::{WindowContext, FullscreenType};
+use sdl2::render::Texture;
 use sdl2::rect::Rect;
 use sdl2::event::Event;
+use std::time::Instant;
 
-pub enum SplashResult {
-    Ok,
-    Quit
-}
+use crate::input::Action;
+use crate::scene::{Context, Scene, SceneBox, Transition};
 
-pub fn show_splash(
-    canvas: &mut WindowCanvas, 
-    texture_creator: &TextureCreator<WindowContext>,
-    event_pump: &mut EventPump,
-    font: &mut Font,
-    message: String,
+// a message fading in and out, any key skips it
+pub struct Splash<'a> {
+    texture: Texture<'a>,
+    // width of the text, relative to the window
     size: f32,
     duration: i32,
-) -> SplashResult {
+    started: Instant,
+    // scene shown afterwards, or None to go back to the one below
+    next: Option<SceneBox<'a>>
+}
 
-    let text_surface = font
-            .render(&message)
-            .blended(Color::WHITE)
-            .map_err(|e| e.to_string())
-            .unwrap();
+impl<'a> Splash<'a> {
+    pub fn init(ctx: &mut Context<'a>, message: String, size: f32, duration: i32, next: Option<SceneBox<'a>>) -> Self {
+        let text_surface = ctx.font
+                .render(&message)
+                .blended(Color::WHITE)
+                .map_err(|e| e.to_string())
+                .unwrap();
 
-    let mut text_texture = texture_creator
-    .create_texture_from_surface(&text_surface).map_err(|e| e.to_string()).unwrap(); 
-    
-    let mut passed: i32 = 0;
+        let texture = ctx.texture_creator
+        .create_texture_from_surface(&text_surface).map_err(|e| e.to_string()).unwrap();
 
-    loop {
+        Self { texture, size, duration, started: Instant::now(), next }
+    }
 
-        for event in event_pump.poll_iter() {
-            match event {
-                Event::Quit {..} => { return SplashResult::Quit },
-                Event::KeyDown { keycode: Some(Keycode::F11), ..} => {
-                    match canvas.window().fullscreen_state() {
-                        FullscreenType::Off => { canvas.window_mut().set_fullscreen(FullscreenType::True).unwrap() },
-                        _ => { canvas.window_mut().set_fullscreen(FullscreenType::Off).unwrap() }
-                    }
-                },
-                Event::KeyDown {..}
-                | Event::MouseButtonDown {..} => { return SplashResult::Ok },
-                _ => {}
-            }
+    fn finish(&mut self) -> Transition<'a> {
+        match self.next.take() {
+            Some(next) => Transition::Replace(next),
+            None => Transition::Pop
         }
+    }
+}
+
+impl<'a> Scene<'a> for Splash<'a> {
+    fn enter(&mut self, _ctx: &mut Context<'a>) {
+        self.started = Instant::now();
+    }
 
-        if passed >= duration {
-            return SplashResult::Ok;
+    fn handle_event(&mut self, _ctx: &mut Context<'a>, event: &Event, actions: &[Action]) -> Transition<'a> {
+        if actions.contains(&Action::ToggleFullscreen) {
+            return Transition::None;
+        }
+        match event {
+            Event::KeyDown {..}
+            | Event::MouseButtonDown {..}
+            | Event::ControllerButtonDown {..} => self.finish(),
+            _ => Transition::None
         }
+    }
+
+    fn update(&mut self, _ctx: &mut Context<'a>) -> Transition<'a> {
+        match self.started.elapsed().as_millis() as i32 >= self.duration {
+            true => self.finish(),
+            false => Transition::None
+        }
+    }
+
+    fn render(&mut self, ctx: &mut Context<'a>, _alpha: f32) {
+        let canvas = &mut ctx.canvas;
+        let passed = (self.started.elapsed().as_millis() as i32).min(self.duration);
+        let duration = self.duration;
 
         let (canvas_x, canvas_y) = canvas.output_size().unwrap();
-        let text_width = text_texture.query().width;
-        let text_height = text_texture.query().height;
-        let scale = canvas_x as f32 * size / text_width as f32; 
+        let text_width = self.texture.query().width;
+        let text_height = self.texture.query().height;
+        let scale = canvas_x as f32 * self.size / text_width as f32;
 
         canvas.clear();
 
@@ -70,22 +85,17 @@ pub fn show_splash(
         if passed*3/2 >= duration {
             alpha = (255 - 255 * (passed * 3 - duration * 2) / duration) as u8;
         }
-        text_texture.set_alpha_mod(alpha);
+        self.texture.set_alpha_mod(alpha);
 
         canvas.copy(
-            &text_texture,
-            None, 
-            Rect::new( 
+            &self.texture,
+            None,
+            Rect::new(
                 (canvas_x as f32 / 2.0 - text_width as f32 * scale / 2.0) as i32,
                 (canvas_y as f32 / 2.0 - text_height as f32 * scale / 2.0) as i32,
                 (text_width as f32 * scale) as u32,
                 (text_height as f32 * scale) as u32
             )
         ).unwrap();
-        
-        canvas.present();
-
-        passed += 50;
-        std::thread::sleep(std::time::Duration::from_millis(50));
     }
-}
\ No newline at end of file
+}
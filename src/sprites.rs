@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+use std::path::Path;
+use serde::Deserialize;
+use sdl2::image::LoadTexture;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{Texture, TextureCreator, WindowCanvas};
+use sdl2::video::WindowContext;
+
+// sheet with every sprite, level themes can replace some of them with a sheet in a folder named after the level
+const SHEET: &str = "resources/images/sprites.json";
+
+// where a sprite is in the atlas
+#[derive(Deserialize)]
+struct Frame {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    // where it's drawn relative to the top corner of its tile, in art space
+    #[serde(default)]
+    offset: (i32, i32)
+}
+
+// frames shown one after another, each for `duration` milliseconds
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SpriteAnimation {
+    pub frames: Vec<String>,
+    #[serde(default)]
+    pub duration: u64
+}
+
+impl SpriteAnimation {
+    // frame shown after some time, in milliseconds
+    pub fn frame_at(&self, millis: u64) -> &str {
+        let frame = millis / self.duration.max(1);
+        &self.frames[frame as usize % self.frames.len()]
+    }
+}
+
+// what a character is doing, each pose is an animation named `<character>_<pose>`, e.g. "police_walk";
+// characters face right, and are mirrored when facing left
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Pose {
+    Idle,
+    Walk,
+    Alert,
+    Caught,
+    Victory
+}
+
+impl Pose {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Pose::Idle => "idle",
+            Pose::Walk => "walk",
+            Pose::Alert => "alert",
+            Pose::Caught => "caught",
+            Pose::Victory => "victory"
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SheetFile {
+    // atlas, relative to the json file
+    image: String,
+    frames: HashMap<String, Frame>,
+    #[serde(default)]
+    animations: HashMap<String, SpriteAnimation>
+}
+
+pub struct Sprites<'a> {
+    // later sheets replace frames and animations of earlier ones
+    sheets: Vec<(Texture<'a>, SheetFile)>
+}
+
+impl<'a> Sprites<'a> {
+    // the common sheet, then the theme's sheet if it has one
+    pub fn load(texture_creator: &'a TextureCreator<WindowContext>, theme: Option<&str>) -> Self {
+        let mut sheets = vec![load_sheet(texture_creator, Path::new(SHEET))];
+        if let Some(theme) = theme {
+            let path = Path::new(SHEET).with_file_name(theme).join("sprites.json");
+            if path.exists() {
+                sheets.push(load_sheet(texture_creator, &path));
+            }
+        }
+        Self { sheets }
+    }
+
+    fn frame(&self, name: &str) -> (usize, &Frame) {
+        self.sheets
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(i, (_, sheet))| sheet.frames.get(name).map(|frame| (i, frame)))
+            .unwrap_or_else(|| panic!("Missing sprite '{name}'"))
+    }
+
+    // a character's animation for a pose, it just stands idle in poses it has no animation for
+    pub fn pose(&self, character: &str, pose: Pose) -> SpriteAnimation {
+        let name = format!("{character}_{}", pose.name());
+        match self.sheets.iter().rev().find_map(|(_, sheet)| sheet.animations.get(&name)) {
+            Some(animation) => animation.clone(),
+            None if pose != Pose::Idle => self.pose(character, Pose::Idle),
+            None => panic!("Missing animation '{name}'")
+        }
+    }
+
+    pub fn offset(&self, name: &str) -> (i32, i32) {
+        self.frame(name).1.offset
+    }
+
+    pub fn size(&self, name: &str) -> (u32, u32) {
+        let (_, frame) = self.frame(name);
+        (frame.width, frame.height)
+    }
+
+    // copy a frame onto the canvas, tinted with color
+    pub fn draw(&mut self, canvas: &mut WindowCanvas, name: &str, dst: Rect, flipped: bool, color: Color) {
+        let (i, frame) = self.frame(name);
+        let src = Rect::new(frame.x, frame.y, frame.width, frame.height);
+        let texture = &mut self.sheets[i].0;
+        texture.set_color_mod(color.r, color.g, color.b);
+        canvas.copy_ex(texture, src, dst, 0.0, None, flipped, false).unwrap();
+        texture.set_color_mod(255, 255, 255);
+    }
+}
+
+fn load_sheet<'a>(texture_creator: &'a TextureCreator<WindowContext>, path: &Path) -> (Texture<'a>, SheetFile) {
+    println!("Loading '{}'", path.display());
+    let source = std::fs::read_to_string(path).expect("Failed to read sprite sheet");
+    let sheet: SheetFile = serde_json::from_str(&source).expect("Failed to parse sprite sheet");
+    let texture = texture_creator.load_texture(path.with_file_name(&sheet.image)).unwrap();
+    (texture, sheet)
+}
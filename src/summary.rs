@@ -0,0 +1,111 @@
+extern crate sdl2;
+
+use sdl2::ttf::Font;
+use sdl2::pixels::Color;
+use sdl2::render::Texture;
+use sdl2::rect::Rect;
+use sdl2::event::Event;
+use std::time::Instant;
+
+use crate::input::Action;
+use crate::scene::{Context, Scene, SceneBox, Transition};
+
+// ignore input for a moment, so the click that finished the level doesn't skip the summary
+const INPUT_DELAY: u128 = 500;
+
+// level-complete screen: a title followed by lines that are either achieved (green), not achieved (gray),
+// or just information (white)
+pub struct Summary<'a> {
+    title_texture: Texture<'a>,
+    line_textures: Vec<Texture<'a>>,
+    hint_texture: Texture<'a>,
+    started: Instant,
+    next: Option<SceneBox<'a>>
+}
+
+impl<'a> Summary<'a> {
+    pub fn init(ctx: &mut Context<'a>, title: String, lines: Vec<(String, Option<bool>)>, next: SceneBox<'a>) -> Self {
+        let texture_creator = ctx.texture_creator;
+        let render_text = |font: &mut Font, text: &str, color: Color| -> Texture<'a> {
+            let surface = font
+                .render(text)
+                .blended(color)
+                .map_err(|e| e.to_string())
+                .unwrap();
+            texture_creator.create_texture_from_surface(&surface).map_err(|e| e.to_string()).unwrap()
+        };
+
+        let title_texture = render_text(&mut ctx.font, &title, Color::WHITE);
+        let line_textures: Vec<Texture> = lines
+            .iter()
+            .map(|(text, achieved)| {
+                match achieved {
+                    Some(true) => render_text(&mut ctx.font, &format!("[x] {text}"), Color::RGB(153, 229, 80)),
+                    Some(false) => render_text(&mut ctx.font, &format!("[ ] {text}"), Color::RGB(128, 128, 128)),
+                    None => render_text(&mut ctx.font, text, Color::WHITE)
+                }
+            })
+            .collect();
+        let hint_texture = render_text(&mut ctx.font, "Press any key", Color::RGB(128, 128, 128));
+
+        Self { title_texture, line_textures, hint_texture, started: Instant::now(), next: Some(next) }
+    }
+}
+
+impl<'a> Scene<'a> for Summary<'a> {
+    fn enter(&mut self, _ctx: &mut Context<'a>) {
+        self.started = Instant::now();
+    }
+
+    fn handle_event(&mut self, _ctx: &mut Context<'a>, event: &Event, actions: &[Action]) -> Transition<'a> {
+        if actions.contains(&Action::ToggleFullscreen) || self.started.elapsed().as_millis() < INPUT_DELAY {
+            return Transition::None;
+        }
+        match event {
+            Event::KeyDown {..}
+            | Event::MouseButtonDown {..}
+            | Event::ControllerButtonDown {..} => match self.next.take() {
+                Some(next) => Transition::Replace(next),
+                None => Transition::None
+            },
+            _ => Transition::None
+        }
+    }
+
+    fn update(&mut self, _ctx: &mut Context<'a>) -> Transition<'a> {
+        Transition::None
+    }
+
+    fn render(&mut self, ctx: &mut Context<'a>, _alpha: f32) {
+        let canvas = &mut ctx.canvas;
+        let passed = self.started.elapsed().as_millis();
+
+        let (canvas_x, canvas_y) = canvas.output_size().unwrap();
+        // line height in pixels, title is twice as tall
+        let line_height = (canvas_y as usize / (self.line_textures.len() * 3 / 2 + 8)).max(12) as i32;
+
+        canvas.set_draw_color(Color::BLACK);
+        canvas.clear();
+
+        let mut draw = |texture: &Texture, y: i32, height: i32| {
+            let query = texture.query();
+            let width = query.width as i32 * height / query.height as i32;
+            canvas.copy(
+                texture,
+                None,
+                Rect::new(canvas_x as i32 / 2 - width / 2, y, width as u32, height as u32)
+            ).unwrap();
+        };
+
+        let mut y = line_height * 2;
+        draw(&self.title_texture, y, line_height * 2);
+        y += line_height * 3;
+        for texture in self.line_textures.iter() {
+            draw(texture, y, line_height);
+            y += line_height * 3 / 2;
+        }
+        if passed >= INPUT_DELAY {
+            draw(&self.hint_texture, canvas_y as i32 - line_height * 2, line_height * 2 / 3);
+        }
+    }
+}
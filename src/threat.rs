@@ -0,0 +1,75 @@
+use crate::level::State;
+use crate::entity::{Entity, Sight};
+
+// threat scores at which the music moves up to the next level
+const THRESHOLDS: [u32; 2] = [2, 6];
+// calm turns needed before the music is allowed to move down a level
+const COOLDOWN_TURNS: u32 = 3;
+// threat levels as the HUD shows them
+const NAMES: [&str; 3] = ["Calm", "Suspicious", "Alert"];
+// pursuers closer than this add to the threat even when they can't see the cat
+const NEAR_DISTANCE: f32 = 6.0;
+
+// how dangerous the cat's situation is right now: robots that see it, pursuers close by,
+// and commandos still hunting for it
+pub fn score(state: &State) -> u32 {
+    let player = state.player.get_position();
+    let tiles = &state.tilemap.tiles;
+    let light = state.lights.visibility(player);
+    let near = |distance: f32| (NEAR_DISTANCE - distance).max(0.0) as u32;
+    let mut score = 0;
+    for citizen in state.citizens.iter() {
+        if citizen.sees_in_light(player, tiles, light) { score += 1 }
+    }
+    for policeman in state.policemen.iter() {
+        if policeman.sees_in_light(player, tiles, light) { score += 3 }
+        score += near(policeman.distance_to(player)) / 2;
+    }
+    for commando in state.commandos.iter() {
+        if commando.sees_in_light(player, tiles, light) { score += 3 }
+        if commando.chasing() { score += 2 }
+        score += near(commando.distance_to(player)) / 2;
+    }
+    score
+}
+
+// threat level from 0 (calm) to THRESHOLDS.len(), with hysteresis: it rises as soon as the score
+// crosses a threshold, but only falls after the score has stayed below it for a few turns
+pub struct Threat {
+    pub level: usize,
+    calm_turns: u32
+}
+
+impl Threat {
+    pub fn init() -> Self {
+        Self { level: 0, calm_turns: 0 }
+    }
+
+    pub fn update(&mut self, score: u32) {
+        let target = THRESHOLDS.iter().filter(|&&threshold| score >= threshold).count();
+        if target >= self.level {
+            self.level = target;
+            self.calm_turns = 0;
+            return;
+        }
+        self.calm_turns += 1;
+        if self.calm_turns >= COOLDOWN_TURNS {
+            self.level -= 1;
+            self.calm_turns = 0;
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        NAMES[self.level.min(NAMES.len() - 1)]
+    }
+
+    // calm turns it takes for the threat to go down a level, None when it's as low as it gets
+    pub fn turns_left(&self) -> Option<u32> {
+        (self.level > 0).then(|| COOLDOWN_TURNS - self.calm_turns)
+    }
+
+    // the track for the current level, from a list ordered from calmest to most intense
+    pub fn track<'a>(&self, tracks: &'a [String]) -> &'a str {
+        &tracks[self.level.min(tracks.len() - 1)]
+    }
+}
@@ -0,0 +1,32 @@
+use sdl2::render::{Texture, TextureCreator};
+use sdl2::video::WindowContext;
+
+use crate::hud::Hud;
+use crate::sprites::Sprites;
+
+// what a level is drawn with: its sprites, a texture the world is drawn into at pixel-art resolution,
+// which is then scaled up to the window, and the HUD on top
+pub struct View<'a> {
+    pub sprites: Sprites<'a>,
+    pub target: Option<Texture<'a>>,
+    pub hud: Hud<'a>,
+    texture_creator: &'a TextureCreator<WindowContext>
+}
+
+impl<'a> View<'a> {
+    pub fn init(texture_creator: &'a TextureCreator<WindowContext>, sprites: Sprites<'a>) -> Self {
+        Self { sprites, target: None, hud: Hud::init(), texture_creator }
+    }
+
+    // make sure the target is at least this big, it only grows so zooming doesn't create a texture every frame
+    pub fn fit_target(&mut self, width: u32, height: u32) {
+        let (old_width, old_height) = match &self.target {
+            Some(target) => (target.query().width, target.query().height),
+            None => (0, 0)
+        };
+        if old_width >= width && old_height >= height { return }
+        let (width, height) = (width.max(old_width), height.max(old_height));
+        println!("Creating {width}x{height} render target");
+        self.target = Some(self.texture_creator.create_texture_target(None, width, height).unwrap());
+    }
+}
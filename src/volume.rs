@@ -0,0 +1,89 @@
+use std::time::{Duration, Instant};
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, Texture, TextureCreator, WindowCanvas};
+use sdl2::ttf::Font;
+use sdl2::video::WindowContext;
+
+use crate::input::Action;
+use crate::mixer::Mixer;
+use crate::settings::{Settings, MASTER_VOLUME_STEP};
+
+// how long the indicator stays on screen after the volume changes
+const INDICATOR_DURATION: Duration = Duration::from_millis(1500);
+
+// apply the volume settings to the mixer
+pub fn apply(settings: &Settings, music_mixer: &mut Mixer) {
+    music_mixer.set_volume(settings.master(), settings.music_volume, settings.effects_volume);
+}
+
+// handle the mute and volume hotkeys, returns true if the volume changed
+pub fn handle_action(action: Action, settings: &mut Settings, music_mixer: &mut Mixer) -> bool {
+    match action {
+        Action::Mute => { settings.muted = !settings.muted },
+        Action::VolumeUp => {
+            settings.muted = false;
+            settings.master_volume = (settings.master_volume + MASTER_VOLUME_STEP).min(100);
+        },
+        Action::VolumeDown => {
+            settings.master_volume = settings.master_volume.saturating_sub(MASTER_VOLUME_STEP);
+        },
+        _ => { return false }
+    }
+    apply(settings, music_mixer);
+    settings.save();
+    true
+}
+
+// "Volume 70%" box in the top right corner, shown for a moment after each change
+pub struct Indicator<'a> {
+    texture: Option<Texture<'a>>,
+    shown: Instant
+}
+
+impl<'a> Indicator<'a> {
+    pub fn init() -> Self {
+        Self { texture: None, shown: Instant::now() }
+    }
+
+    pub fn show(&mut self, texture_creator: &'a TextureCreator<WindowContext>, font: &mut Font, settings: &Settings) {
+        let text = match settings.muted {
+            true => "Muted".to_string(),
+            false => format!("Volume {}%", settings.master_volume)
+        };
+        let surface = font
+            .render(&text)
+            .blended(Color::WHITE)
+            .map_err(|e| e.to_string())
+            .unwrap();
+        self.texture = Some(texture_creator.create_texture_from_surface(&surface).map_err(|e| e.to_string()).unwrap());
+        self.shown = Instant::now();
+    }
+
+    pub fn render(&self, canvas: &mut WindowCanvas) {
+        let Some(texture) = &self.texture else { return };
+        if self.shown.elapsed() > INDICATOR_DURATION { return }
+
+        let (canvas_x, canvas_y) = canvas.output_size().unwrap();
+        let height = (canvas_y / 20).max(12);
+        let query = texture.query();
+        let width = query.width * height / query.height;
+        let padding = height / 3;
+        let background = Rect::new(
+            (canvas_x - width - padding * 3) as i32,
+            padding as i32,
+            width + padding * 2,
+            height + padding * 2
+        );
+
+        canvas.set_blend_mode(BlendMode::Blend);
+        canvas.set_draw_color(Color::RGBA(0, 0, 0, 160));
+        canvas.fill_rect(background).unwrap();
+        canvas.set_blend_mode(BlendMode::None);
+        canvas.copy(
+            texture,
+            None,
+            Rect::new(background.x() + padding as i32, background.y() + padding as i32, width, height)
+        ).unwrap();
+    }
+}